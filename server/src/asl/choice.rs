@@ -0,0 +1,586 @@
+//! The Choice rule grammar.
+//!
+//! A rule is a boolean expression over the state input: `And`, `Or` and `Not`
+//! nest arbitrarily, and every leaf compares one `Variable` against a literal
+//! or against another path (`...Path` operators). JSONata machines use a
+//! single `Condition` string instead. The JSON shape is one flat object per
+//! node, so (de)serialization goes through `Map` and checks the grammar as it
+//! goes.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// A top-level entry of a Choice state's `Choices`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "Map<String, Value>", into = "Map<String, Value>")]
+pub struct ChoiceRule {
+    pub condition: Condition,
+    pub next: String,
+    pub comment: Option<String>,
+    /// JSONata `Output` of the Choice state when this rule matches.
+    pub output: Option<Value>,
+    /// Variables assigned when this rule matches.
+    pub assign: Option<Value>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "Map<String, Value>", into = "Map<String, Value>")]
+pub enum Condition {
+    And(Vec<Condition>),
+    Or(Vec<Condition>),
+    Not(Box<Condition>),
+    Comparison {
+        variable: String,
+        operator: Operator,
+        operand: Operand,
+    },
+    /// JSONata `Condition`, e.g. `{% $states.input.total > 100 %}`.
+    Expression(String),
+}
+
+/// The right-hand side of a comparison.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Operand {
+    Literal(Value),
+    /// From a `...Path` operator: compare against the value at this path.
+    Path(String),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Operator {
+    StringEquals,
+    StringLessThan,
+    StringGreaterThan,
+    StringLessThanEquals,
+    StringGreaterThanEquals,
+    StringMatches,
+    NumericEquals,
+    NumericLessThan,
+    NumericGreaterThan,
+    NumericLessThanEquals,
+    NumericGreaterThanEquals,
+    BooleanEquals,
+    TimestampEquals,
+    TimestampLessThan,
+    TimestampGreaterThan,
+    TimestampLessThanEquals,
+    TimestampGreaterThanEquals,
+    IsNull,
+    IsPresent,
+    IsNumeric,
+    IsString,
+    IsBoolean,
+    IsTimestamp,
+}
+
+impl Operator {
+    pub const ALL: [Operator; 23] = [
+        Operator::StringEquals,
+        Operator::StringLessThan,
+        Operator::StringGreaterThan,
+        Operator::StringLessThanEquals,
+        Operator::StringGreaterThanEquals,
+        Operator::StringMatches,
+        Operator::NumericEquals,
+        Operator::NumericLessThan,
+        Operator::NumericGreaterThan,
+        Operator::NumericLessThanEquals,
+        Operator::NumericGreaterThanEquals,
+        Operator::BooleanEquals,
+        Operator::TimestampEquals,
+        Operator::TimestampLessThan,
+        Operator::TimestampGreaterThan,
+        Operator::TimestampLessThanEquals,
+        Operator::TimestampGreaterThanEquals,
+        Operator::IsNull,
+        Operator::IsPresent,
+        Operator::IsNumeric,
+        Operator::IsString,
+        Operator::IsBoolean,
+        Operator::IsTimestamp,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Operator::StringEquals => "StringEquals",
+            Operator::StringLessThan => "StringLessThan",
+            Operator::StringGreaterThan => "StringGreaterThan",
+            Operator::StringLessThanEquals => "StringLessThanEquals",
+            Operator::StringGreaterThanEquals => "StringGreaterThanEquals",
+            Operator::StringMatches => "StringMatches",
+            Operator::NumericEquals => "NumericEquals",
+            Operator::NumericLessThan => "NumericLessThan",
+            Operator::NumericGreaterThan => "NumericGreaterThan",
+            Operator::NumericLessThanEquals => "NumericLessThanEquals",
+            Operator::NumericGreaterThanEquals => "NumericGreaterThanEquals",
+            Operator::BooleanEquals => "BooleanEquals",
+            Operator::TimestampEquals => "TimestampEquals",
+            Operator::TimestampLessThan => "TimestampLessThan",
+            Operator::TimestampGreaterThan => "TimestampGreaterThan",
+            Operator::TimestampLessThanEquals => "TimestampLessThanEquals",
+            Operator::TimestampGreaterThanEquals => "TimestampGreaterThanEquals",
+            Operator::IsNull => "IsNull",
+            Operator::IsPresent => "IsPresent",
+            Operator::IsNumeric => "IsNumeric",
+            Operator::IsString => "IsString",
+            Operator::IsBoolean => "IsBoolean",
+            Operator::IsTimestamp => "IsTimestamp",
+        }
+    }
+
+    /// Type tests and `StringMatches` have no `...Path` form.
+    pub fn has_path_form(self) -> bool {
+        !matches!(
+            self,
+            Operator::StringMatches
+                | Operator::IsNull
+                | Operator::IsPresent
+                | Operator::IsNumeric
+                | Operator::IsString
+                | Operator::IsBoolean
+                | Operator::IsTimestamp
+        )
+    }
+
+    /// Whether `value` is a valid literal operand for this operator.
+    fn accepts(self, value: &Value) -> bool {
+        match self {
+            Operator::NumericEquals
+            | Operator::NumericLessThan
+            | Operator::NumericGreaterThan
+            | Operator::NumericLessThanEquals
+            | Operator::NumericGreaterThanEquals => value.is_number(),
+            Operator::BooleanEquals
+            | Operator::IsNull
+            | Operator::IsPresent
+            | Operator::IsNumeric
+            | Operator::IsString
+            | Operator::IsBoolean
+            | Operator::IsTimestamp => value.is_boolean(),
+            _ => value.is_string(),
+        }
+    }
+
+    fn symbol(self) -> &'static str {
+        match self {
+            Operator::StringEquals
+            | Operator::NumericEquals
+            | Operator::BooleanEquals
+            | Operator::TimestampEquals => "==",
+            Operator::StringLessThan | Operator::NumericLessThan | Operator::TimestampLessThan => {
+                "<"
+            }
+            Operator::StringGreaterThan
+            | Operator::NumericGreaterThan
+            | Operator::TimestampGreaterThan => ">",
+            Operator::StringLessThanEquals
+            | Operator::NumericLessThanEquals
+            | Operator::TimestampLessThanEquals => "<=",
+            Operator::StringGreaterThanEquals
+            | Operator::NumericGreaterThanEquals
+            | Operator::TimestampGreaterThanEquals => ">=",
+            Operator::StringMatches => "matches",
+            Operator::IsNull => "null",
+            Operator::IsPresent => "present",
+            Operator::IsNumeric => "numeric",
+            Operator::IsString => "a string",
+            Operator::IsBoolean => "a boolean",
+            Operator::IsTimestamp => "a timestamp",
+        }
+    }
+
+    /// Resolves a rule key such as `NumericGreaterThanPath`.
+    fn parse(key: &str) -> Option<(Operator, bool)> {
+        Operator::ALL.into_iter().find_map(|operator| {
+            if key == operator.name() {
+                Some((operator, false))
+            } else if operator.has_path_form() && key.strip_suffix("Path") == Some(operator.name())
+            {
+                Some((operator, true))
+            } else {
+                None
+            }
+        })
+    }
+
+    fn is_type_test(self) -> bool {
+        !self.has_path_form() && self != Operator::StringMatches
+    }
+}
+
+impl Condition {
+    /// Every `Variable` and path operand the condition reads.
+    pub fn paths(&self) -> Vec<&str> {
+        let mut paths = Vec::new();
+        self.collect_paths(&mut paths);
+        paths
+    }
+
+    fn collect_paths<'a>(&'a self, paths: &mut Vec<&'a str>) {
+        match self {
+            Condition::And(conditions) | Condition::Or(conditions) => conditions
+                .iter()
+                .for_each(|condition| condition.collect_paths(paths)),
+            Condition::Not(condition) => condition.collect_paths(paths),
+            Condition::Comparison {
+                variable, operand, ..
+            } => {
+                paths.push(variable);
+                if let Operand::Path(path) = operand {
+                    paths.push(path);
+                }
+            }
+            Condition::Expression(_) => {}
+        }
+    }
+}
+
+impl TryFrom<Map<String, Value>> for ChoiceRule {
+    type Error = String;
+
+    fn try_from(mut rule: Map<String, Value>) -> Result<Self, Self::Error> {
+        let next = match rule.remove("Next") {
+            Some(Value::String(next)) => next,
+            Some(_) => return Err(String::from("Choice rule \"Next\" must be a string")),
+            None => return Err(String::from("Choice rule is missing \"Next\"")),
+        };
+        let comment = match rule.remove("Comment") {
+            Some(Value::String(comment)) => Some(comment),
+            Some(_) => return Err(String::from("Choice rule \"Comment\" must be a string")),
+            None => None,
+        };
+        let output = rule.remove("Output");
+        let assign = rule.remove("Assign");
+        Ok(ChoiceRule {
+            condition: Condition::try_from(rule)?,
+            next,
+            comment,
+            output,
+            assign,
+        })
+    }
+}
+
+impl From<ChoiceRule> for Map<String, Value> {
+    fn from(rule: ChoiceRule) -> Self {
+        let mut map = Map::from(rule.condition);
+        map.insert(String::from("Next"), Value::String(rule.next));
+        if let Some(comment) = rule.comment {
+            map.insert(String::from("Comment"), Value::String(comment));
+        }
+        if let Some(output) = rule.output {
+            map.insert(String::from("Output"), output);
+        }
+        if let Some(assign) = rule.assign {
+            map.insert(String::from("Assign"), assign);
+        }
+        map
+    }
+}
+
+fn nested(value: Value) -> Result<Condition, String> {
+    match value {
+        Value::Object(map) => Condition::try_from(map),
+        _ => Err(String::from("nested Choice rules must be objects")),
+    }
+}
+
+impl TryFrom<Map<String, Value>> for Condition {
+    type Error = String;
+
+    fn try_from(mut rule: Map<String, Value>) -> Result<Self, Self::Error> {
+        for combinator in ["And", "Or", "Not", "Condition"] {
+            let Some(value) = rule.remove(combinator) else {
+                continue;
+            };
+            if let Some(key) = rule.keys().next() {
+                return Err(format!(
+                    "\"{key}\" cannot be combined with \"{combinator}\""
+                ));
+            }
+            return match (combinator, value) {
+                ("Not", value) => Ok(Condition::Not(Box::new(nested(value)?))),
+                ("Condition", Value::String(expression)) => Ok(Condition::Expression(expression)),
+                ("Condition", _) => Err(String::from("\"Condition\" must be a string")),
+                (_, Value::Array(conditions)) if !conditions.is_empty() => {
+                    let conditions = conditions
+                        .into_iter()
+                        .map(nested)
+                        .collect::<Result<Vec<_>, _>>()?;
+                    Ok(if combinator == "And" {
+                        Condition::And(conditions)
+                    } else {
+                        Condition::Or(conditions)
+                    })
+                }
+                (_, _) => Err(format!("\"{combinator}\" must be a non-empty array")),
+            };
+        }
+
+        let variable = match rule.remove("Variable") {
+            Some(Value::String(variable)) => variable,
+            Some(_) => return Err(String::from("\"Variable\" must be a string")),
+            None => {
+                return Err(String::from(
+                    "Choice rule has no \"Variable\" or combinator",
+                ))
+            }
+        };
+        let mut comparisons = rule.into_iter();
+        let (key, value) = comparisons
+            .next()
+            .ok_or_else(|| format!("Choice rule on \"{variable}\" has no comparison operator"))?;
+        if let Some((extra, _)) = comparisons.next() {
+            return Err(format!(
+                "Choice rule on \"{variable}\" has more than one comparison (\"{key}\", \"{extra}\")"
+            ));
+        }
+        let (operator, is_path) = Operator::parse(&key)
+            .ok_or_else(|| format!("unknown comparison operator \"{key}\""))?;
+        let operand = match value {
+            Value::String(path) if is_path => Operand::Path(path),
+            _ if is_path => return Err(format!("\"{key}\" must be a path string")),
+            value if operator.accepts(&value) => Operand::Literal(value),
+            _ => return Err(format!("\"{key}\" has an operand of the wrong type")),
+        };
+        Ok(Condition::Comparison {
+            variable,
+            operator,
+            operand,
+        })
+    }
+}
+
+impl From<Condition> for Map<String, Value> {
+    fn from(condition: Condition) -> Self {
+        let mut map = Map::new();
+        match condition {
+            Condition::And(conditions) => {
+                map.insert(String::from("And"), conditions_to_value(conditions));
+            }
+            Condition::Or(conditions) => {
+                map.insert(String::from("Or"), conditions_to_value(conditions));
+            }
+            Condition::Not(condition) => {
+                map.insert(String::from("Not"), Value::Object((*condition).into()));
+            }
+            Condition::Comparison {
+                variable,
+                operator,
+                operand,
+            } => {
+                map.insert(String::from("Variable"), Value::String(variable));
+                match operand {
+                    Operand::Literal(value) => map.insert(operator.name().to_string(), value),
+                    Operand::Path(path) => {
+                        map.insert(format!("{}Path", operator.name()), Value::String(path))
+                    }
+                };
+            }
+            Condition::Expression(expression) => {
+                map.insert(String::from("Condition"), Value::String(expression));
+            }
+        }
+        map
+    }
+}
+
+fn conditions_to_value(conditions: Vec<Condition>) -> Value {
+    Value::Array(
+        conditions
+            .into_iter()
+            .map(|condition| Value::Object(condition.into()))
+            .collect(),
+    )
+}
+
+impl fmt::Display for Operand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Operand::Literal(value) => write!(f, "{value}"),
+            Operand::Path(path) => write!(f, "{path}"),
+        }
+    }
+}
+
+/// Renders conditions for people, e.g. `$.total > 100 and not ($.vip == true)`.
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let join = |f: &mut fmt::Formatter<'_>, conditions: &[Condition], separator: &str| {
+            for (index, condition) in conditions.iter().enumerate() {
+                if index > 0 {
+                    write!(f, " {separator} ")?;
+                }
+                match condition {
+                    Condition::And(_) | Condition::Or(_) => write!(f, "({condition})")?,
+                    _ => write!(f, "{condition}")?,
+                }
+            }
+            Ok(())
+        };
+        match self {
+            Condition::And(conditions) => join(f, conditions, "and"),
+            Condition::Or(conditions) => join(f, conditions, "or"),
+            Condition::Not(condition) => write!(f, "not ({condition})"),
+            Condition::Comparison {
+                variable,
+                operator,
+                operand: Operand::Literal(Value::Bool(expected)),
+            } if operator.is_type_test() => {
+                let is = if *expected { "is" } else { "is not" };
+                write!(f, "{variable} {is} {}", operator.symbol())
+            }
+            Condition::Comparison {
+                variable,
+                operator,
+                operand,
+            } => write!(f, "{variable} {} {operand}", operator.symbol()),
+            Condition::Expression(expression) => write!(f, "{expression}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn rule(value: Value) -> Result<ChoiceRule, String> {
+        serde_json::from_value(value).map_err(|e| e.to_string())
+    }
+
+    #[test]
+    fn nested_rules_parse_and_round_trip() {
+        let original = json!({
+            "And": [
+                {"Variable": "$.total", "NumericGreaterThanEquals": 100},
+                {"Or": [
+                    {"Variable": "$.country", "StringEquals": "NZ"},
+                    {"Variable": "$.email", "StringMatches": "*@example.com"}
+                ]},
+                {"Not": {"Variable": "$.placedAt", "TimestampLessThanPath": "$.cutoff"}},
+                {"Variable": "$.coupon", "IsNull": false}
+            ],
+            "Next": "Review",
+            "Comment": "big orders"
+        });
+        let parsed = rule(original.clone()).unwrap();
+
+        assert_eq!(parsed.next, "Review");
+        assert_eq!(parsed.comment.as_deref(), Some("big orders"));
+        let Condition::And(conditions) = &parsed.condition else {
+            panic!("expected And");
+        };
+        assert_eq!(
+            conditions[2],
+            Condition::Not(Box::new(Condition::Comparison {
+                variable: String::from("$.placedAt"),
+                operator: Operator::TimestampLessThan,
+                operand: Operand::Path(String::from("$.cutoff")),
+            }))
+        );
+        assert_eq!(
+            parsed.condition.paths(),
+            [
+                "$.total",
+                "$.country",
+                "$.email",
+                "$.placedAt",
+                "$.cutoff",
+                "$.coupon"
+            ]
+        );
+        assert_eq!(serde_json::to_value(&parsed).unwrap(), original);
+    }
+
+    #[test]
+    fn every_operator_round_trips() {
+        for operator in Operator::ALL {
+            let literal = match operator {
+                Operator::NumericEquals
+                | Operator::NumericLessThan
+                | Operator::NumericGreaterThan
+                | Operator::NumericLessThanEquals
+                | Operator::NumericGreaterThanEquals => json!(1.5),
+                _ if operator.accepts(&json!(true)) => json!(true),
+                _ => json!("2024-01-01T00:00:00Z"),
+            };
+            let mut keys = vec![(operator.name().to_string(), literal)];
+            if operator.has_path_form() {
+                keys.push((format!("{}Path", operator.name()), json!("$.other")));
+            }
+            for (key, operand) in keys {
+                let original = json!({"Variable": "$.value", key.clone(): operand, "Next": "A"});
+                let parsed = rule(original.clone()).unwrap_or_else(|e| panic!("{key}: {e}"));
+                assert_eq!(serde_json::to_value(&parsed).unwrap(), original, "{key}");
+            }
+        }
+    }
+
+    #[test]
+    fn malformed_rules_are_rejected() {
+        let cases = [
+            json!({"Variable": "$.a", "StringEquals": "x"}),
+            json!({"Variable": "$.a", "Next": "A"}),
+            json!({"Variable": "$.a", "StringEquals": "x", "NumericEquals": 1, "Next": "A"}),
+            json!({"Variable": "$.a", "StringContains": "x", "Next": "A"}),
+            json!({"Variable": "$.a", "NumericEquals": "1", "Next": "A"}),
+            json!({"Variable": "$.a", "StringMatchesPath": "$.b", "Next": "A"}),
+            json!({"Variable": "$.a", "IsPresentPath": "$.b", "Next": "A"}),
+            json!({"And": [], "Next": "A"}),
+            json!({"Not": [{"Variable": "$.a", "IsNull": true}], "Next": "A"}),
+            json!({"And": [{"Variable": "$.a", "IsNull": true}], "Variable": "$.a", "Next": "A"}),
+            json!({"Next": "A"}),
+            json!({"Condition": "{% true %}", "Variable": "$.a", "Next": "A"}),
+            json!({"Condition": "{% true %}", "IsNull": true, "Next": "A"}),
+        ];
+        for case in cases {
+            assert!(rule(case.clone()).is_err(), "{case} should not parse");
+        }
+    }
+
+    #[test]
+    fn jsonata_conditions_are_kept() {
+        let original = json!({"Condition": "{% $states.input.total > 100 %}", "Next": "A"});
+        let parsed = rule(original.clone()).unwrap();
+        assert_eq!(
+            parsed.condition,
+            Condition::Expression(String::from("{% $states.input.total > 100 %}"))
+        );
+        assert_eq!(serde_json::to_value(&parsed).unwrap(), original);
+
+        let original = json!({
+            "Condition": "{% $states.input.total > 100 %}",
+            "Output": "{% $states.input.order %}",
+            "Assign": {"large": true},
+            "Next": "A"
+        });
+        let parsed = rule(original.clone()).unwrap();
+        assert_eq!(parsed.output, Some(json!("{% $states.input.order %}")));
+        assert_eq!(parsed.assign, Some(json!({"large": true})));
+        assert_eq!(serde_json::to_value(&parsed).unwrap(), original);
+    }
+
+    #[test]
+    fn conditions_render_for_people() {
+        let parsed = rule(json!({
+            "Or": [
+                {"And": [
+                    {"Variable": "$.total", "NumericGreaterThan": 100},
+                    {"Variable": "$.currency", "StringEqualsPath": "$.defaultCurrency"}
+                ]},
+                {"Not": {"Variable": "$.vip", "IsPresent": true}},
+                {"Variable": "$.email", "StringMatches": "*@example.com"}
+            ],
+            "Next": "A"
+        }))
+        .unwrap();
+        assert_eq!(
+            parsed.condition.to_string(),
+            "($.total > 100 and $.currency == $.defaultCurrency) or not ($.vip is present) \
+             or $.email matches \"*@example.com\""
+        );
+    }
+}
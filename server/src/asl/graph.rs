@@ -0,0 +1,537 @@
+//! Definitions as explicit graphs, so every client draws the same structure.
+//!
+//! Node ids are JSON pointers into the definition (`/States/Fan/Branches/0/States/A`),
+//! which keeps them unique across nesting and matches the paths of
+//! [`Warning`]s. Parallel branches and Map processors become subgraphs of the
+//! state that runs them; their edges never leave the subgraph.
+
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+use serde_json::Value;
+
+use super::lenient::{self, Lenient, LenientDefinition, Machine, Warning};
+use super::{Branch, ChoiceRule, State};
+use crate::diff::escape;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub enum EdgeKind {
+    Next,
+    Default,
+    Choice,
+    Catch,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Edge {
+    pub from: String,
+    pub to: String,
+    pub kind: EdgeKind,
+    /// The rule of a Choice edge, or the errors a Catch edge handles.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Node {
+    pub id: String,
+    pub name: String,
+    /// The state's `Type`; whatever the definition says for untyped states.
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub start: bool,
+    pub end: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub subgraphs: Vec<Subgraph>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Subgraph {
+    /// `Branch 1`, `Branch 2`, ... for Parallel; `ItemProcessor` or `Iterator` for Map.
+    pub label: String,
+    #[serde(flatten)]
+    pub graph: Graph,
+}
+
+#[derive(Debug, Default, PartialEq, Serialize)]
+pub struct Graph {
+    pub nodes: Vec<Node>,
+    pub edges: Vec<Edge>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct DefinitionGraph {
+    #[serde(flatten)]
+    pub graph: Graph,
+    pub warnings: Vec<Warning>,
+}
+
+impl DefinitionGraph {
+    pub fn new(definition: LenientDefinition) -> Self {
+        DefinitionGraph {
+            graph: Graph::lenient("", definition.start_at.as_deref(), &definition.states),
+            warnings: definition.warnings,
+        }
+    }
+}
+
+impl Graph {
+    fn machine(path: &str, machine: &Branch) -> Self {
+        let mut graph = Graph::default();
+        let id_of = |name: &str| format!("{path}/States/{}", escape(name));
+        for (name, state) in &machine.states {
+            let start = machine.start_at == *name;
+            graph.add(&id_of(name), name, state, &[], start, &id_of);
+        }
+        graph
+    }
+
+    fn lenient(
+        path: &str,
+        start_at: Option<&str>,
+        states: &BTreeMap<String, Lenient<State>>,
+    ) -> Self {
+        let mut graph = Graph::default();
+        let id_of = |name: &str| format!("{path}/States/{}", escape(name));
+        for (name, state) in states {
+            let id = id_of(name);
+            let start = start_at == Some(name.as_str());
+            match state {
+                Lenient::Typed(state) => graph.add(&id, name, state, &[], start, &id_of),
+                Lenient::Nested(nested) => {
+                    graph.add(&id, name, &nested.state, &nested.machines, start, &id_of)
+                }
+                Lenient::Raw(raw) => graph.add_raw(&id, name, raw, start, &id_of),
+            }
+        }
+        graph
+    }
+
+    /// `nested` holds the leniently parsed states of machines that `state`
+    /// carries with their `States` left empty.
+    fn add(
+        &mut self,
+        id: &str,
+        name: &str,
+        state: &State,
+        nested: &[Machine],
+        start: bool,
+        id_of: &dyn Fn(&str) -> String,
+    ) {
+        let mut edge = |to: &str, kind: EdgeKind, label: Option<String>| {
+            self.edges.push(Edge {
+                from: id.to_string(),
+                to: id_of(to),
+                kind,
+                label,
+            })
+        };
+        if let Some(next) = state.next() {
+            edge(next, EdgeKind::Next, None);
+        }
+        if let State::Choice(choice) = state {
+            for rule in &choice.choices {
+                edge(
+                    &rule.next,
+                    EdgeKind::Choice,
+                    Some(rule.condition.to_string()),
+                );
+            }
+            if let Some(default) = &choice.default {
+                edge(default, EdgeKind::Default, None);
+            }
+        }
+        for catcher in state.catchers() {
+            edge(
+                &catcher.next,
+                EdgeKind::Catch,
+                Some(catcher.error_equals.join(", ")),
+            );
+        }
+
+        let subgraph = |pointer: String, branch: &Branch| {
+            let path = format!("{id}{pointer}");
+            match nested.iter().find(|machine| machine.pointer == pointer) {
+                Some(machine) => Graph::lenient(&path, Some(&branch.start_at), &machine.states),
+                None => Graph::machine(&path, branch),
+            }
+        };
+        let subgraphs = match state {
+            State::Parallel(parallel) => parallel
+                .branches
+                .iter()
+                .enumerate()
+                .map(|(index, branch)| Subgraph {
+                    label: format!("Branch {}", index + 1),
+                    graph: subgraph(format!("/Branches/{index}"), branch),
+                })
+                .collect(),
+            State::Map(map) => [
+                ("ItemProcessor", &map.item_processor),
+                ("Iterator", &map.iterator),
+            ]
+            .into_iter()
+            .filter_map(|(key, processor)| {
+                processor.as_ref().map(|processor| Subgraph {
+                    label: key.to_string(),
+                    graph: subgraph(format!("/{key}"), processor),
+                })
+            })
+            .collect(),
+            _ => Vec::new(),
+        };
+
+        self.nodes.push(Node {
+            id: id.to_string(),
+            name: name.to_string(),
+            kind: state.kind().to_string(),
+            start,
+            end: state.is_end(),
+            subgraphs,
+        });
+    }
+
+    /// Every node, parents before the nodes of their subgraphs.
+    pub fn all_nodes(&self) -> Vec<&Node> {
+        let mut nodes = Vec::new();
+        for node in &self.nodes {
+            nodes.push(node);
+            for subgraph in &node.subgraphs {
+                nodes.extend(subgraph.graph.all_nodes());
+            }
+        }
+        nodes
+    }
+
+    /// A state the typed model rejected, drawn from whatever of its
+    /// transitions and nested machines are well-formed.
+    fn add_raw(
+        &mut self,
+        id: &str,
+        name: &str,
+        raw: &Value,
+        start: bool,
+        id_of: &dyn Fn(&str) -> String,
+    ) {
+        let mut edge = |to: &Value, kind: EdgeKind, label: Option<String>| {
+            if let Value::String(to) = to {
+                self.edges.push(Edge {
+                    from: id.to_string(),
+                    to: id_of(to),
+                    kind,
+                    label,
+                })
+            }
+        };
+        let each = |key: &str| {
+            raw.get(key)
+                .and_then(Value::as_array)
+                .map(Vec::as_slice)
+                .unwrap_or_default()
+        };
+        if let Some(next) = raw.get("Next") {
+            edge(next, EdgeKind::Next, None);
+        }
+        for rule in each("Choices") {
+            let label = serde_json::from_value::<ChoiceRule>(rule.clone())
+                .ok()
+                .map(|rule| rule.condition.to_string());
+            if let Some(next) = rule.get("Next") {
+                edge(next, EdgeKind::Choice, label);
+            }
+        }
+        if let Some(default) = raw.get("Default") {
+            edge(default, EdgeKind::Default, None);
+        }
+        for catcher in each("Catch") {
+            let label = catcher
+                .get("ErrorEquals")
+                .and_then(Value::as_array)
+                .map(|errors| {
+                    errors
+                        .iter()
+                        .filter_map(Value::as_str)
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                });
+            if let Some(next) = catcher.get("Next") {
+                edge(next, EdgeKind::Catch, label);
+            }
+        }
+
+        let subgraphs = lenient::raw_machines(raw)
+            .into_iter()
+            .map(|machine| {
+                let label = match machine
+                    .pointer
+                    .strip_prefix("/Branches/")
+                    .and_then(|index| index.parse::<usize>().ok())
+                {
+                    Some(index) => format!("Branch {}", index + 1),
+                    None => machine.pointer.trim_start_matches('/').to_string(),
+                };
+                let start_at = raw
+                    .pointer(&format!("{}/StartAt", machine.pointer))
+                    .and_then(Value::as_str);
+                Subgraph {
+                    label,
+                    graph: Graph::lenient(
+                        &format!("{id}{}", machine.pointer),
+                        start_at,
+                        &machine.states,
+                    ),
+                }
+            })
+            .collect();
+
+        self.nodes.push(Node {
+            id: id.to_string(),
+            name: name.to_string(),
+            kind: raw
+                .get("Type")
+                .and_then(Value::as_str)
+                .unwrap_or("Unknown")
+                .to_string(),
+            start,
+            end: raw.get("End") == Some(&Value::Bool(true)),
+            subgraphs,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::asl::lenient;
+    use serde_json::json;
+
+    fn nodes_by_id(graph: &Graph) -> BTreeMap<&str, &Node> {
+        graph
+            .all_nodes()
+            .into_iter()
+            .map(|node| (node.id.as_str(), node))
+            .collect()
+    }
+
+    fn graph(document: &str) -> DefinitionGraph {
+        DefinitionGraph::new(lenient::parse(document).unwrap())
+    }
+
+    #[test]
+    fn parallel_branches_are_subgraphs() {
+        let parsed = graph(include_str!("../../fixtures/asl/parallel.asl.json"));
+        let nodes = nodes_by_id(&parsed.graph);
+
+        let notify = nodes["/States/Notify"];
+        assert!(notify.start);
+        assert_eq!(notify.kind, "Parallel");
+        assert_eq!(notify.subgraphs.len(), 2);
+        assert_eq!(notify.subgraphs[1].label, "Branch 2");
+        assert_eq!(
+            notify.subgraphs[1].graph.edges,
+            [Edge {
+                from: String::from("/States/Notify/Branches/1/States/SendSms"),
+                to: String::from("/States/Notify/Branches/1/States/SmsSent"),
+                kind: EdgeKind::Next,
+                label: None,
+            }]
+        );
+        assert!(nodes["/States/Notify/Branches/0/States/SendEmail"].start);
+        assert!(nodes["/States/Notify/Branches/1/States/SmsSent"].end);
+
+        let catch = parsed
+            .graph
+            .edges
+            .iter()
+            .find(|edge| edge.kind == EdgeKind::Catch)
+            .unwrap();
+        assert_eq!(catch.to, "/States/NotifyFailed");
+        assert_eq!(catch.label.as_deref(), Some("States.ALL"));
+    }
+
+    #[test]
+    fn choice_edges_carry_their_rule() {
+        let parsed = graph(
+            &json!({
+                "StartAt": "Route",
+                "States": {
+                    "Route": {
+                        "Type": "Choice",
+                        "Choices": [
+                            {"Variable": "$.total", "NumericGreaterThan": 100, "Next": "Review"}
+                        ],
+                        "Default": "Done"
+                    },
+                    "Review": {"Type": "Pass", "Next": "Done"},
+                    "Done": {"Type": "Succeed"}
+                }
+            })
+            .to_string(),
+        );
+        let edges: Vec<(&str, EdgeKind, Option<&str>)> = parsed
+            .graph
+            .edges
+            .iter()
+            .map(|edge| (edge.to.as_str(), edge.kind, edge.label.as_deref()))
+            .collect();
+        assert_eq!(
+            edges,
+            [
+                ("/States/Done", EdgeKind::Next, None),
+                ("/States/Review", EdgeKind::Choice, Some("$.total > 100")),
+                ("/States/Done", EdgeKind::Default, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn map_processors_and_untyped_states_are_kept() {
+        let parsed = graph(
+            &json!({
+                "StartAt": "Each",
+                "States": {
+                    "Each": {
+                        "Type": "Map",
+                        "ItemProcessor": {
+                            "StartAt": "Work",
+                            "States": {"Work": {"Type": "Pass", "End": true}}
+                        },
+                        "Next": "Beam"
+                    },
+                    "Beam": {"Type": "Teleport", "Next": "Each/Done"},
+                    "Each/Done": {"Type": "Succeed"}
+                }
+            })
+            .to_string(),
+        );
+        let nodes = nodes_by_id(&parsed.graph);
+        assert_eq!(nodes["/States/Each"].subgraphs[0].label, "ItemProcessor");
+        assert!(nodes.contains_key("/States/Each/ItemProcessor/States/Work"));
+        assert_eq!(nodes["/States/Beam"].kind, "Teleport");
+        assert!(parsed
+            .graph
+            .edges
+            .iter()
+            .any(|edge| edge.from == "/States/Beam" && edge.to == "/States/Each~1Done"));
+        assert_eq!(parsed.warnings.len(), 1);
+    }
+
+    #[test]
+    fn typed_parents_of_bad_nested_states_keep_their_subgraphs() {
+        let parsed = graph(
+            &json!({
+                "StartAt": "Each",
+                "States": {
+                    "Each": {
+                        "Type": "Map",
+                        "ItemProcessor": {
+                            "StartAt": "Work",
+                            "States": {
+                                "Work": {"Type": "Pass", "Next": "Beam"},
+                                "Beam": {"Type": "Teleport", "Next": "Work"}
+                            }
+                        },
+                        "End": true
+                    }
+                }
+            })
+            .to_string(),
+        );
+        let nodes = nodes_by_id(&parsed.graph);
+        assert_eq!(nodes["/States/Each"].kind, "Map");
+        assert!(nodes["/States/Each"].end);
+        assert!(nodes["/States/Each/ItemProcessor/States/Work"].start);
+        assert_eq!(
+            nodes["/States/Each/ItemProcessor/States/Beam"].kind,
+            "Teleport"
+        );
+        let edges: Vec<(&str, &str)> = parsed.graph.nodes[0].subgraphs[0]
+            .graph
+            .edges
+            .iter()
+            .map(|edge| (edge.from.as_str(), edge.to.as_str()))
+            .collect();
+        assert_eq!(
+            edges,
+            [
+                (
+                    "/States/Each/ItemProcessor/States/Beam",
+                    "/States/Each/ItemProcessor/States/Work"
+                ),
+                (
+                    "/States/Each/ItemProcessor/States/Work",
+                    "/States/Each/ItemProcessor/States/Beam"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn untyped_states_keep_their_transitions_and_machines() {
+        let parsed = graph(
+            &json!({
+                "StartAt": "Fork",
+                "States": {
+                    "Fork": {
+                        "Type": "Fork",
+                        "Branches": [{
+                            "StartAt": "A",
+                            "States": {"A": {"Type": "Pass", "Next": "B"}, "B": {"Type": "Succeed"}}
+                        }],
+                        "Catch": [{"ErrorEquals": ["States.ALL"], "Next": "Failed"}],
+                        "Next": "Route"
+                    },
+                    "Route": {
+                        "Type": "Switch",
+                        "Choices": [{"Variable": "$.ok", "BooleanEquals": true, "Next": "Done"}],
+                        "Default": "Failed"
+                    },
+                    "Done": {"Type": "Succeed"},
+                    "Failed": {"Type": "Fail"}
+                }
+            })
+            .to_string(),
+        );
+        let edges: Vec<(&str, &str, EdgeKind, Option<&str>)> = parsed
+            .graph
+            .edges
+            .iter()
+            .map(|edge| {
+                (
+                    edge.from.as_str(),
+                    edge.to.as_str(),
+                    edge.kind,
+                    edge.label.as_deref(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            edges,
+            [
+                ("/States/Fork", "/States/Route", EdgeKind::Next, None),
+                (
+                    "/States/Fork",
+                    "/States/Failed",
+                    EdgeKind::Catch,
+                    Some("States.ALL")
+                ),
+                (
+                    "/States/Route",
+                    "/States/Done",
+                    EdgeKind::Choice,
+                    Some("$.ok == true")
+                ),
+                ("/States/Route", "/States/Failed", EdgeKind::Default, None),
+            ]
+        );
+
+        let nodes = nodes_by_id(&parsed.graph);
+        let fork = nodes["/States/Fork"];
+        assert_eq!(fork.subgraphs[0].label, "Branch 1");
+        assert!(nodes["/States/Fork/Branches/0/States/A"].start);
+        assert_eq!(
+            fork.subgraphs[0].graph.edges[0].to,
+            "/States/Fork/Branches/0/States/B"
+        );
+    }
+}
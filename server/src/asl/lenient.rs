@@ -0,0 +1,382 @@
+//! Best-effort parsing for definitions we only display.
+//!
+//! Definitions come from the service, so they were valid when they were
+//! created, but the model here may lag behind the language. Anything that
+//! does not fit the typed model is kept as raw JSON and reported as a
+//! warning instead of failing the whole parse. States nested in a Parallel
+//! or Map are parsed the same way, so one bad child does not cost the typed
+//! model of its parent and siblings.
+
+use std::collections::BTreeMap;
+
+use serde::de::Error as _;
+use serde::{ser, Serialize, Serializer};
+use serde_json::{Map, Value};
+
+use super::State;
+use crate::diff::escape;
+
+/// A state that matched the typed model, or its JSON as-is.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum Lenient<T> {
+    Typed(T),
+    /// A Parallel or Map whose own fields are typed but some nested state is not.
+    Nested(Nested<T>),
+    Raw(Value),
+}
+
+/// A state parsed with the `States` of its nested machines left empty.
+///
+/// The machines are parsed leniently on their own and put back in place when
+/// serializing, so the JSON round-trips.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Nested<T> {
+    pub state: T,
+    pub machines: Vec<Machine>,
+}
+
+/// The states of a Parallel branch or a Map processor.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Machine {
+    /// JSON pointer relative to the state: `/Branches/0`, `/ItemProcessor`, ...
+    pub pointer: String,
+    pub states: BTreeMap<String, Lenient<State>>,
+}
+
+impl<T: Serialize> Serialize for Nested<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serde_json::to_value(&self.state).map_err(ser::Error::custom)?;
+        for machine in &self.machines {
+            let states = serde_json::to_value(&machine.states).map_err(ser::Error::custom)?;
+            if let Some(Value::Object(target)) = state.pointer_mut(&machine.pointer) {
+                target.insert(String::from("States"), states);
+            }
+        }
+        state.serialize(serializer)
+    }
+}
+
+/// Something in the definition that could not be typed, located by JSON pointer.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Warning {
+    pub path: String,
+    pub message: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct LenientDefinition {
+    #[serde(rename = "Comment", skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    #[serde(rename = "StartAt", skip_serializing_if = "Option::is_none")]
+    pub start_at: Option<String>,
+    #[serde(rename = "States")]
+    pub states: BTreeMap<String, Lenient<State>>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+    pub warnings: Vec<Warning>,
+}
+
+/// Parses `document`, failing only if it is not a JSON object.
+pub fn parse(document: &str) -> Result<LenientDefinition, serde_json::Error> {
+    let Value::Object(mut definition) = serde_json::from_str(document)? else {
+        return Err(serde_json::Error::custom(
+            "state machine definition must be a JSON object",
+        ));
+    };
+    let mut warnings = Vec::new();
+    let mut warn = |path: &str, message: &str| {
+        warnings.push(Warning {
+            path: path.to_string(),
+            message: message.to_string(),
+        })
+    };
+
+    let comment = match definition.remove("Comment") {
+        Some(Value::String(comment)) => Some(comment),
+        Some(other) => {
+            warn("/Comment", "Comment is not a string");
+            definition.insert(String::from("Comment"), other);
+            None
+        }
+        None => None,
+    };
+    let start_at = match definition.remove("StartAt") {
+        Some(Value::String(start_at)) => Some(start_at),
+        Some(other) => {
+            warn("/StartAt", "StartAt is not a string");
+            definition.insert(String::from("StartAt"), other);
+            None
+        }
+        None => {
+            warn("/StartAt", "StartAt is missing");
+            None
+        }
+    };
+    let states = match definition.remove("States") {
+        Some(Value::Object(states)) => states,
+        Some(other) => {
+            warn("/States", "States is not an object");
+            definition.insert(String::from("States"), other);
+            Map::new()
+        }
+        None => {
+            warn("/States", "States is missing");
+            Map::new()
+        }
+    };
+
+    let states = typed_states("/States", states, &mut warnings);
+    if let Some(start_at) = &start_at {
+        if !states.contains_key(start_at) {
+            warnings.push(Warning {
+                path: String::from("/StartAt"),
+                message: format!("StartAt names a state that does not exist: {start_at:?}"),
+            });
+        }
+    }
+
+    Ok(LenientDefinition {
+        comment,
+        start_at,
+        states,
+        extra: definition,
+        warnings,
+    })
+}
+
+fn typed_states(
+    path: &str,
+    states: Map<String, Value>,
+    warnings: &mut Vec<Warning>,
+) -> BTreeMap<String, Lenient<State>> {
+    states
+        .into_iter()
+        .map(|(name, raw)| {
+            let path = format!("{}/{}", path, escape(&name));
+            let state = match serde_json::from_value::<State>(raw.clone()) {
+                Ok(state) => Lenient::Typed(state),
+                Err(e) => match nested(&path, &raw, warnings) {
+                    Some(nested) => Lenient::Nested(nested),
+                    None => {
+                        nested_warnings(&path, &raw, warnings);
+                        warnings.push(Warning {
+                            path,
+                            message: e.to_string(),
+                        });
+                        Lenient::Raw(raw)
+                    }
+                },
+            };
+            (name, state)
+        })
+        .collect()
+}
+
+/// Where a Parallel or Map state keeps machines that have a `States` object.
+fn machine_pointers(state: &Value) -> Vec<String> {
+    let mut pointers = Vec::new();
+    if let Some(Value::Array(branches)) = state.get("Branches") {
+        pointers.extend((0..branches.len()).map(|index| format!("/Branches/{index}")));
+    }
+    for key in ["ItemProcessor", "Iterator"] {
+        if state.get(key).is_some() {
+            pointers.push(format!("/{key}"));
+        }
+    }
+    pointers.retain(|pointer| {
+        matches!(
+            state.pointer(&format!("{pointer}/States")),
+            Some(Value::Object(_))
+        )
+    });
+    pointers
+}
+
+/// Types a state whose only problems are in its nested machines.
+///
+/// Returns `None` when the state itself does not fit the typed model.
+fn nested(path: &str, raw: &Value, warnings: &mut Vec<Warning>) -> Option<Nested<State>> {
+    let pointers = machine_pointers(raw);
+    if pointers.is_empty() {
+        return None;
+    }
+    let mut stripped = raw.clone();
+    let mut machines = Vec::new();
+    for pointer in pointers {
+        let machine = stripped.pointer_mut(&pointer)?.as_object_mut()?;
+        if let Some(Value::Object(states)) =
+            machine.insert(String::from("States"), Value::Object(Map::new()))
+        {
+            machines.push((pointer, states));
+        }
+    }
+    let state = serde_json::from_value::<State>(stripped).ok()?;
+    let machines = machines
+        .into_iter()
+        .map(|(pointer, states)| Machine {
+            states: typed_states(&format!("{path}{pointer}/States"), states, warnings),
+            pointer,
+        })
+        .collect();
+    Some(Nested { state, machines })
+}
+
+/// The machines of an untyped Parallel or Map, parsed leniently.
+///
+/// Their warnings were already reported by [`parse`].
+pub fn raw_machines(state: &Value) -> Vec<Machine> {
+    machine_pointers(state)
+        .into_iter()
+        .filter_map(|pointer| {
+            let Some(Value::Object(states)) = state.pointer(&format!("{pointer}/States")) else {
+                return None;
+            };
+            let states = typed_states("", states.clone(), &mut Vec::new());
+            Some(Machine { pointer, states })
+        })
+        .collect()
+}
+
+/// Collects warnings from the machines of a Parallel or Map state that is
+/// itself untyped; they are reported before the state's own warning.
+fn nested_warnings(path: &str, state: &Value, warnings: &mut Vec<Warning>) {
+    for pointer in machine_pointers(state) {
+        if let Some(Value::Object(states)) = state.pointer(&format!("{pointer}/States")) {
+            typed_states(&format!("{path}{pointer}/States"), states.clone(), warnings);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn valid_definitions_have_no_warnings() {
+        let document = include_str!("../../fixtures/asl/orders.asl.json");
+        let parsed = parse(document).unwrap();
+        assert!(parsed.warnings.is_empty(), "{:?}", parsed.warnings);
+        assert!(parsed
+            .states
+            .values()
+            .all(|state| matches!(state, Lenient::Typed(_))));
+
+        let mut round_tripped = serde_json::to_value(&parsed).unwrap();
+        round_tripped.as_object_mut().unwrap().remove("warnings");
+        assert_eq!(
+            round_tripped,
+            serde_json::from_str::<Value>(document).unwrap()
+        );
+    }
+
+    #[test]
+    fn unknown_states_are_kept_raw_with_a_warning() {
+        let document = json!({
+            "StartAt": "First",
+            "States": {
+                "First": {"Type": "Teleport", "Destination": "moon", "Next": "Done"},
+                "Done": {"Type": "Succeed"}
+            }
+        });
+        let parsed = parse(&document.to_string()).unwrap();
+
+        assert_eq!(parsed.comment, None);
+        assert_eq!(
+            parsed.states["First"],
+            Lenient::Raw(document["States"]["First"].clone())
+        );
+        assert!(matches!(
+            parsed.states["Done"],
+            Lenient::Typed(State::Succeed(_))
+        ));
+        assert_eq!(parsed.warnings.len(), 1);
+        assert_eq!(parsed.warnings[0].path, "/States/First");
+        assert!(parsed.warnings[0].message.contains("Teleport"));
+        assert_eq!(
+            serde_json::to_value(&parsed).unwrap()["States"],
+            document["States"]
+        );
+    }
+
+    #[test]
+    fn nested_problems_are_reported_where_they_are() {
+        let document = json!({
+            "Comment": 7,
+            "StartAt": "Missing",
+            "States": {
+                "Fan": {
+                    "Type": "Parallel",
+                    "Branches": [
+                        {"StartAt": "A", "States": {"A": {"Type": "Succeed"}}},
+                        {"StartAt": "B", "States": {"B/1": {"Type": "Task"}}}
+                    ],
+                    "End": true
+                }
+            }
+        });
+        let parsed = parse(&document.to_string()).unwrap();
+        let paths: Vec<&str> = parsed
+            .warnings
+            .iter()
+            .map(|warning| warning.path.as_str())
+            .collect();
+        assert_eq!(
+            paths,
+            ["/Comment", "/States/Fan/Branches/1/States/B~11", "/StartAt"]
+        );
+        assert_eq!(parsed.extra["Comment"], 7);
+    }
+
+    #[test]
+    fn a_bad_nested_state_keeps_its_parent_and_siblings_typed() {
+        let document = json!({
+            "StartAt": "Fan",
+            "States": {
+                "Fan": {
+                    "Type": "Parallel",
+                    "Branches": [{
+                        "StartAt": "Good",
+                        "States": {
+                            "Good": {"Type": "Pass", "Next": "Bad"},
+                            "Bad": {"Type": "Teleport", "End": true}
+                        }
+                    }],
+                    "End": true
+                }
+            }
+        });
+        let parsed = parse(&document.to_string()).unwrap();
+
+        let Lenient::Nested(fan) = &parsed.states["Fan"] else {
+            panic!("expected a nested state, got {:?}", parsed.states["Fan"]);
+        };
+        assert!(matches!(fan.state, State::Parallel(_)));
+        assert!(fan.state.is_end());
+        assert_eq!(fan.machines.len(), 1);
+        assert_eq!(fan.machines[0].pointer, "/Branches/0");
+        let branch = &fan.machines[0].states;
+        assert!(matches!(branch["Good"], Lenient::Typed(State::Pass(_))));
+        assert!(matches!(branch["Bad"], Lenient::Raw(_)));
+
+        let paths: Vec<&str> = parsed
+            .warnings
+            .iter()
+            .map(|warning| warning.path.as_str())
+            .collect();
+        assert_eq!(paths, ["/States/Fan/Branches/0/States/Bad"]);
+        assert_eq!(
+            serde_json::to_value(&parsed).unwrap()["States"],
+            document["States"]
+        );
+    }
+
+    #[test]
+    fn only_malformed_json_fails() {
+        assert!(parse("{\"StartAt\": ").is_err());
+        assert!(parse("[]").is_err());
+        let parsed = parse("{}").unwrap();
+        assert_eq!(parsed.warnings.len(), 2);
+    }
+}
@@ -0,0 +1,657 @@
+//! Typed Amazon States Language model.
+//!
+//! Every state type has its own struct. Fields the model does not know about
+//! are kept in `extra`, and fields that may be explicitly `null` in ASL
+//! (`InputPath`, `OutputPath`, `ResultPath`) are `Option<Option<_>>` so that
+//! "absent" and "null" survive a round trip.
+
+pub mod choice;
+pub mod graph;
+pub mod lenient;
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::{Map, Number, Value};
+
+pub use choice::ChoiceRule;
+
+/// Distinguishes a present `null` (`Some(None)`) from an absent field (`None`).
+fn nullable<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct StateMachineDefinition {
+    #[serde(rename = "Comment", default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    #[serde(rename = "StartAt")]
+    pub start_at: String,
+    #[serde(rename = "States")]
+    pub states: BTreeMap<String, State>,
+    #[serde(rename = "Version", default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    #[serde(
+        rename = "TimeoutSeconds",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub timeout_seconds: Option<u64>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// A Parallel branch, or the `Iterator` / `ItemProcessor` of a Map state.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Branch {
+    #[serde(rename = "Comment", default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    #[serde(rename = "StartAt")]
+    pub start_at: String,
+    #[serde(rename = "States")]
+    pub states: BTreeMap<String, State>,
+    /// Only on `ItemProcessor`: `{"Mode": "INLINE" | "DISTRIBUTED", "ExecutionType": ...}`.
+    #[serde(
+        rename = "ProcessorConfig",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub processor_config: Option<Value>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// One state, tagged by its `Type` field.
+///
+/// Definitions are parsed once per request and never stored in bulk, so the
+/// size difference between variants is not worth boxing over.
+#[allow(clippy::large_enum_variant)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "Type")]
+pub enum State {
+    Task(TaskState),
+    Pass(PassState),
+    Choice(ChoiceState),
+    Wait(WaitState),
+    Succeed(SucceedState),
+    Fail(FailState),
+    Parallel(ParallelState),
+    Map(MapState),
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Retrier {
+    #[serde(rename = "ErrorEquals")]
+    pub error_equals: Vec<String>,
+    #[serde(
+        rename = "IntervalSeconds",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub interval_seconds: Option<u64>,
+    #[serde(
+        rename = "MaxAttempts",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub max_attempts: Option<u64>,
+    #[serde(
+        rename = "BackoffRate",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub backoff_rate: Option<Number>,
+    #[serde(
+        rename = "MaxDelaySeconds",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub max_delay_seconds: Option<u64>,
+    #[serde(
+        rename = "JitterStrategy",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub jitter_strategy: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Catcher {
+    #[serde(rename = "ErrorEquals")]
+    pub error_equals: Vec<String>,
+    #[serde(rename = "Next")]
+    pub next: String,
+    #[serde(
+        rename = "ResultPath",
+        default,
+        deserialize_with = "nullable",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub result_path: Option<Option<String>>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TaskState {
+    #[serde(rename = "Comment", default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    #[serde(rename = "Resource")]
+    pub resource: String,
+    #[serde(rename = "Next", default, skip_serializing_if = "Option::is_none")]
+    pub next: Option<String>,
+    #[serde(rename = "End", default, skip_serializing_if = "Option::is_none")]
+    pub end: Option<bool>,
+    #[serde(
+        rename = "InputPath",
+        default,
+        deserialize_with = "nullable",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub input_path: Option<Option<String>>,
+    #[serde(
+        rename = "OutputPath",
+        default,
+        deserialize_with = "nullable",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub output_path: Option<Option<String>>,
+    #[serde(
+        rename = "Parameters",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub parameters: Option<Value>,
+    #[serde(
+        rename = "ResultSelector",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub result_selector: Option<Value>,
+    #[serde(
+        rename = "ResultPath",
+        default,
+        deserialize_with = "nullable",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub result_path: Option<Option<String>>,
+    #[serde(rename = "Retry", default, skip_serializing_if = "Option::is_none")]
+    pub retry: Option<Vec<Retrier>>,
+    #[serde(rename = "Catch", default, skip_serializing_if = "Option::is_none")]
+    pub catch: Option<Vec<Catcher>>,
+    #[serde(
+        rename = "TimeoutSeconds",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub timeout_seconds: Option<u64>,
+    #[serde(
+        rename = "TimeoutSecondsPath",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub timeout_seconds_path: Option<String>,
+    #[serde(
+        rename = "HeartbeatSeconds",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub heartbeat_seconds: Option<u64>,
+    #[serde(
+        rename = "HeartbeatSecondsPath",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub heartbeat_seconds_path: Option<String>,
+    #[serde(
+        rename = "Credentials",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub credentials: Option<Value>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PassState {
+    #[serde(rename = "Comment", default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    #[serde(rename = "Next", default, skip_serializing_if = "Option::is_none")]
+    pub next: Option<String>,
+    #[serde(rename = "End", default, skip_serializing_if = "Option::is_none")]
+    pub end: Option<bool>,
+    #[serde(
+        rename = "InputPath",
+        default,
+        deserialize_with = "nullable",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub input_path: Option<Option<String>>,
+    #[serde(
+        rename = "OutputPath",
+        default,
+        deserialize_with = "nullable",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub output_path: Option<Option<String>>,
+    #[serde(
+        rename = "Parameters",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub parameters: Option<Value>,
+    #[serde(rename = "Result", default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+    #[serde(
+        rename = "ResultPath",
+        default,
+        deserialize_with = "nullable",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub result_path: Option<Option<String>>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ChoiceState {
+    #[serde(rename = "Comment", default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    #[serde(rename = "Choices")]
+    pub choices: Vec<ChoiceRule>,
+    #[serde(rename = "Default", default, skip_serializing_if = "Option::is_none")]
+    pub default: Option<String>,
+    #[serde(
+        rename = "InputPath",
+        default,
+        deserialize_with = "nullable",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub input_path: Option<Option<String>>,
+    #[serde(
+        rename = "OutputPath",
+        default,
+        deserialize_with = "nullable",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub output_path: Option<Option<String>>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct WaitState {
+    #[serde(rename = "Comment", default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    #[serde(rename = "Next", default, skip_serializing_if = "Option::is_none")]
+    pub next: Option<String>,
+    #[serde(rename = "End", default, skip_serializing_if = "Option::is_none")]
+    pub end: Option<bool>,
+    #[serde(
+        rename = "InputPath",
+        default,
+        deserialize_with = "nullable",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub input_path: Option<Option<String>>,
+    #[serde(
+        rename = "OutputPath",
+        default,
+        deserialize_with = "nullable",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub output_path: Option<Option<String>>,
+    #[serde(rename = "Seconds", default, skip_serializing_if = "Option::is_none")]
+    pub seconds: Option<u64>,
+    #[serde(
+        rename = "SecondsPath",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub seconds_path: Option<String>,
+    #[serde(rename = "Timestamp", default, skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<String>,
+    #[serde(
+        rename = "TimestampPath",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub timestamp_path: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SucceedState {
+    #[serde(rename = "Comment", default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    #[serde(
+        rename = "InputPath",
+        default,
+        deserialize_with = "nullable",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub input_path: Option<Option<String>>,
+    #[serde(
+        rename = "OutputPath",
+        default,
+        deserialize_with = "nullable",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub output_path: Option<Option<String>>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FailState {
+    #[serde(rename = "Comment", default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    #[serde(rename = "Error", default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(rename = "ErrorPath", default, skip_serializing_if = "Option::is_none")]
+    pub error_path: Option<String>,
+    #[serde(rename = "Cause", default, skip_serializing_if = "Option::is_none")]
+    pub cause: Option<String>,
+    #[serde(rename = "CausePath", default, skip_serializing_if = "Option::is_none")]
+    pub cause_path: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ParallelState {
+    #[serde(rename = "Comment", default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    #[serde(rename = "Branches")]
+    pub branches: Vec<Branch>,
+    #[serde(rename = "Next", default, skip_serializing_if = "Option::is_none")]
+    pub next: Option<String>,
+    #[serde(rename = "End", default, skip_serializing_if = "Option::is_none")]
+    pub end: Option<bool>,
+    #[serde(
+        rename = "InputPath",
+        default,
+        deserialize_with = "nullable",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub input_path: Option<Option<String>>,
+    #[serde(
+        rename = "OutputPath",
+        default,
+        deserialize_with = "nullable",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub output_path: Option<Option<String>>,
+    #[serde(
+        rename = "Parameters",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub parameters: Option<Value>,
+    #[serde(
+        rename = "ResultSelector",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub result_selector: Option<Value>,
+    #[serde(
+        rename = "ResultPath",
+        default,
+        deserialize_with = "nullable",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub result_path: Option<Option<String>>,
+    #[serde(rename = "Retry", default, skip_serializing_if = "Option::is_none")]
+    pub retry: Option<Vec<Retrier>>,
+    #[serde(rename = "Catch", default, skip_serializing_if = "Option::is_none")]
+    pub catch: Option<Vec<Catcher>>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MapState {
+    #[serde(rename = "Comment", default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    /// `ItemProcessor`, or the older `Iterator` spelling of it.
+    #[serde(
+        rename = "ItemProcessor",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub item_processor: Option<Branch>,
+    #[serde(rename = "Iterator", default, skip_serializing_if = "Option::is_none")]
+    pub iterator: Option<Branch>,
+    #[serde(rename = "ItemsPath", default, skip_serializing_if = "Option::is_none")]
+    pub items_path: Option<String>,
+    #[serde(
+        rename = "ItemSelector",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub item_selector: Option<Value>,
+    #[serde(
+        rename = "ItemReader",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub item_reader: Option<Value>,
+    #[serde(
+        rename = "ItemBatcher",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub item_batcher: Option<Value>,
+    #[serde(
+        rename = "ResultWriter",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub result_writer: Option<Value>,
+    #[serde(
+        rename = "MaxConcurrency",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub max_concurrency: Option<u64>,
+    #[serde(
+        rename = "MaxConcurrencyPath",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub max_concurrency_path: Option<String>,
+    #[serde(
+        rename = "ToleratedFailurePercentage",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub tolerated_failure_percentage: Option<Number>,
+    #[serde(
+        rename = "ToleratedFailureCount",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub tolerated_failure_count: Option<u64>,
+    #[serde(rename = "Label", default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    #[serde(rename = "Next", default, skip_serializing_if = "Option::is_none")]
+    pub next: Option<String>,
+    #[serde(rename = "End", default, skip_serializing_if = "Option::is_none")]
+    pub end: Option<bool>,
+    #[serde(
+        rename = "InputPath",
+        default,
+        deserialize_with = "nullable",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub input_path: Option<Option<String>>,
+    #[serde(
+        rename = "OutputPath",
+        default,
+        deserialize_with = "nullable",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub output_path: Option<Option<String>>,
+    /// Pre-`ItemSelector` name for the per-item input template.
+    #[serde(
+        rename = "Parameters",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub parameters: Option<Value>,
+    #[serde(
+        rename = "ResultSelector",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub result_selector: Option<Value>,
+    #[serde(
+        rename = "ResultPath",
+        default,
+        deserialize_with = "nullable",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub result_path: Option<Option<String>>,
+    #[serde(rename = "Retry", default, skip_serializing_if = "Option::is_none")]
+    pub retry: Option<Vec<Retrier>>,
+    #[serde(rename = "Catch", default, skip_serializing_if = "Option::is_none")]
+    pub catch: Option<Vec<Catcher>>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl State {
+    /// The state's `Type` field.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            State::Task(_) => "Task",
+            State::Pass(_) => "Pass",
+            State::Choice(_) => "Choice",
+            State::Wait(_) => "Wait",
+            State::Succeed(_) => "Succeed",
+            State::Fail(_) => "Fail",
+            State::Parallel(_) => "Parallel",
+            State::Map(_) => "Map",
+        }
+    }
+
+    /// `Next`, for the state types that have one. Choice states branch instead.
+    pub fn next(&self) -> Option<&str> {
+        match self {
+            State::Task(state) => state.next.as_deref(),
+            State::Pass(state) => state.next.as_deref(),
+            State::Wait(state) => state.next.as_deref(),
+            State::Parallel(state) => state.next.as_deref(),
+            State::Map(state) => state.next.as_deref(),
+            State::Choice(_) | State::Succeed(_) | State::Fail(_) => None,
+        }
+    }
+
+    /// Whether the execution (or branch) may finish in this state.
+    pub fn is_end(&self) -> bool {
+        match self {
+            State::Task(state) => state.end == Some(true),
+            State::Pass(state) => state.end == Some(true),
+            State::Wait(state) => state.end == Some(true),
+            State::Parallel(state) => state.end == Some(true),
+            State::Map(state) => state.end == Some(true),
+            State::Succeed(_) | State::Fail(_) => true,
+            State::Choice(_) => false,
+        }
+    }
+
+    pub fn catchers(&self) -> &[Catcher] {
+        let catch = match self {
+            State::Task(state) => &state.catch,
+            State::Parallel(state) => &state.catch,
+            State::Map(state) => &state.catch,
+            _ => return &[],
+        };
+        catch.as_deref().unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLES: [(&str, &str); 4] = [
+        ("orders", include_str!("../../fixtures/asl/orders.asl.json")),
+        (
+            "parallel",
+            include_str!("../../fixtures/asl/parallel.asl.json"),
+        ),
+        (
+            "distributed-map",
+            include_str!("../../fixtures/asl/distributed-map.asl.json"),
+        ),
+        (
+            "legacy-map",
+            include_str!("../../fixtures/asl/legacy-map.asl.json"),
+        ),
+    ];
+
+    #[test]
+    fn samples_round_trip_losslessly() {
+        for (name, sample) in SAMPLES {
+            let original: Value = serde_json::from_str(sample).unwrap();
+            let definition: StateMachineDefinition = serde_json::from_str(sample)
+                .unwrap_or_else(|e| panic!("{name} failed to parse: {e}"));
+            let round_tripped = serde_json::to_value(&definition).unwrap();
+            assert_eq!(round_tripped, original, "{name} changed in a round trip");
+        }
+    }
+
+    #[test]
+    fn states_are_typed_by_their_type_field() {
+        let definition: StateMachineDefinition = serde_json::from_str(SAMPLES[0].1).unwrap();
+
+        let State::Task(charge) = &definition.states["ChargeCard"] else {
+            panic!("ChargeCard is a Task");
+        };
+        assert_eq!(charge.timeout_seconds, Some(30));
+        assert_eq!(charge.heartbeat_seconds, Some(10));
+        let retry = &charge.retry.as_ref().unwrap()[0];
+        assert_eq!(retry.max_attempts, Some(3));
+        assert_eq!(retry.backoff_rate.as_ref().unwrap().as_f64(), Some(1.5));
+        let catch = &charge.catch.as_ref().unwrap()[0];
+        assert_eq!(catch.next, "PaymentFailed");
+        assert_eq!(catch.result_path, Some(Some(String::from("$.error"))));
+
+        let State::Wait(wait) = &definition.states["WaitForSettlement"] else {
+            panic!("WaitForSettlement is a Wait");
+        };
+        assert_eq!(wait.seconds_path.as_deref(), Some("$.settlementDelay"));
+
+        let State::Fail(fail) = &definition.states["PaymentFailed"] else {
+            panic!("PaymentFailed is a Fail");
+        };
+        assert_eq!(fail.error.as_deref(), Some("PaymentFailed"));
+        assert_eq!(fail.cause.as_deref(), Some("The card was declined"));
+
+        let State::Pass(pass) = &definition.states["Discard"] else {
+            panic!("Discard is a Pass");
+        };
+        assert_eq!(pass.result_path, Some(None));
+    }
+
+    #[test]
+    fn nested_machines_are_typed() {
+        let definition: StateMachineDefinition = serde_json::from_str(SAMPLES[2].1).unwrap();
+        let State::Map(map) = &definition.states["ProcessFiles"] else {
+            panic!("ProcessFiles is a Map");
+        };
+        assert_eq!(map.max_concurrency, Some(100));
+        let processor = map.item_processor.as_ref().unwrap();
+        assert_eq!(processor.start_at, "Transform");
+        assert!(matches!(processor.states["Transform"], State::Task(_)));
+    }
+}
@@ -0,0 +1,327 @@
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use tokio::sync::Semaphore;
+
+use super::{BackendError, StepFunctions};
+use crate::history::{EventResponse, HistoryQuery};
+use crate::model::{
+    CreateStateMachineResponse, ExecutionDescription, ExecutionsQuery, ExecutionsResponse,
+    StartExecutionResponse, StateMachine, StateMachineDescriptor, StateMachineResponse,
+    StopExecutionResponse, UpdateStateMachineResponse,
+};
+
+/// Wraps another backend with a global concurrency cap and a per-call deadline.
+///
+/// The deadline covers waiting for a slot as well as the call itself. When the
+/// deadline passes or the client disconnects, the inner future is dropped,
+/// which cancels the HTTP request or kills the CLI process.
+pub struct Bounded {
+    inner: Arc<dyn StepFunctions>,
+    permits: Semaphore,
+    timeout: Duration,
+}
+
+impl Bounded {
+    pub fn new(inner: Arc<dyn StepFunctions>, max_concurrency: usize, timeout: Duration) -> Self {
+        Bounded {
+            inner,
+            permits: Semaphore::new(max_concurrency),
+            timeout,
+        }
+    }
+
+    async fn guard<T>(
+        &self,
+        call: impl Future<Output = Result<T, BackendError>>,
+    ) -> Result<T, BackendError> {
+        let bounded = async {
+            let _permit = self
+                .permits
+                .acquire()
+                .await
+                .map_err(|e| BackendError::Unavailable(e.to_string()))?;
+            call.await
+        };
+        tokio::time::timeout(self.timeout, bounded)
+            .await
+            .map_err(|_| BackendError::Timeout(self.timeout))?
+    }
+}
+
+#[async_trait]
+impl StepFunctions for Bounded {
+    async fn list_state_machines(
+        &self,
+        region: &str,
+    ) -> Result<StateMachineResponse, BackendError> {
+        self.guard(self.inner.list_state_machines(region)).await
+    }
+
+    async fn describe_state_machine(
+        &self,
+        region: &str,
+        arn: &str,
+    ) -> Result<StateMachine, BackendError> {
+        self.guard(self.inner.describe_state_machine(region, arn))
+            .await
+    }
+
+    async fn list_executions(
+        &self,
+        region: &str,
+        arn: &str,
+        query: &ExecutionsQuery,
+    ) -> Result<ExecutionsResponse, BackendError> {
+        self.guard(self.inner.list_executions(region, arn, query))
+            .await
+    }
+
+    async fn get_execution_history(
+        &self,
+        region: &str,
+        arn: &str,
+        query: &HistoryQuery,
+    ) -> Result<EventResponse, BackendError> {
+        self.guard(self.inner.get_execution_history(region, arn, query))
+            .await
+    }
+
+    async fn describe_execution(
+        &self,
+        region: &str,
+        arn: &str,
+    ) -> Result<ExecutionDescription, BackendError> {
+        self.guard(self.inner.describe_execution(region, arn)).await
+    }
+
+    async fn describe_state_machine_for_execution(
+        &self,
+        region: &str,
+        arn: &str,
+    ) -> Result<StateMachineDescriptor, BackendError> {
+        self.guard(self.inner.describe_state_machine_for_execution(region, arn))
+            .await
+    }
+
+    async fn start_execution(
+        &self,
+        region: &str,
+        arn: &str,
+        name: Option<&str>,
+        input: &str,
+    ) -> Result<StartExecutionResponse, BackendError> {
+        self.guard(self.inner.start_execution(region, arn, name, input))
+            .await
+    }
+
+    async fn stop_execution(
+        &self,
+        region: &str,
+        arn: &str,
+        error: &str,
+        cause: &str,
+    ) -> Result<StopExecutionResponse, BackendError> {
+        self.guard(self.inner.stop_execution(region, arn, error, cause))
+            .await
+    }
+
+    async fn create_state_machine(
+        &self,
+        region: &str,
+        name: &str,
+        definition: &str,
+        role_arn: &str,
+        kind: &str,
+    ) -> Result<CreateStateMachineResponse, BackendError> {
+        self.guard(
+            self.inner
+                .create_state_machine(region, name, definition, role_arn, kind),
+        )
+        .await
+    }
+
+    async fn update_state_machine(
+        &self,
+        region: &str,
+        arn: &str,
+        definition: &str,
+    ) -> Result<UpdateStateMachineResponse, BackendError> {
+        self.guard(self.inner.update_state_machine(region, arn, definition))
+            .await
+    }
+
+    async fn delete_state_machine(&self, region: &str, arn: &str) -> Result<(), BackendError> {
+        self.guard(self.inner.delete_state_machine(region, arn))
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::FakeBackend;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Hangs on `list_state_machines`, counting how many calls got past the cap.
+    struct Hanging {
+        started: AtomicUsize,
+        fake: FakeBackend,
+    }
+
+    #[async_trait]
+    impl StepFunctions for Hanging {
+        async fn list_state_machines(
+            &self,
+            _region: &str,
+        ) -> Result<StateMachineResponse, BackendError> {
+            self.started.fetch_add(1, Ordering::SeqCst);
+            std::future::pending().await
+        }
+
+        async fn describe_state_machine(
+            &self,
+            region: &str,
+            arn: &str,
+        ) -> Result<StateMachine, BackendError> {
+            self.fake.describe_state_machine(region, arn).await
+        }
+
+        async fn list_executions(
+            &self,
+            region: &str,
+            arn: &str,
+            query: &ExecutionsQuery,
+        ) -> Result<ExecutionsResponse, BackendError> {
+            self.fake.list_executions(region, arn, query).await
+        }
+
+        async fn get_execution_history(
+            &self,
+            region: &str,
+            arn: &str,
+            query: &HistoryQuery,
+        ) -> Result<EventResponse, BackendError> {
+            self.fake.get_execution_history(region, arn, query).await
+        }
+
+        async fn describe_execution(
+            &self,
+            region: &str,
+            arn: &str,
+        ) -> Result<ExecutionDescription, BackendError> {
+            self.fake.describe_execution(region, arn).await
+        }
+
+        async fn describe_state_machine_for_execution(
+            &self,
+            region: &str,
+            arn: &str,
+        ) -> Result<StateMachineDescriptor, BackendError> {
+            self.fake
+                .describe_state_machine_for_execution(region, arn)
+                .await
+        }
+
+        async fn start_execution(
+            &self,
+            region: &str,
+            arn: &str,
+            name: Option<&str>,
+            input: &str,
+        ) -> Result<StartExecutionResponse, BackendError> {
+            self.fake.start_execution(region, arn, name, input).await
+        }
+
+        async fn stop_execution(
+            &self,
+            region: &str,
+            arn: &str,
+            error: &str,
+            cause: &str,
+        ) -> Result<StopExecutionResponse, BackendError> {
+            self.fake.stop_execution(region, arn, error, cause).await
+        }
+
+        async fn create_state_machine(
+            &self,
+            region: &str,
+            name: &str,
+            definition: &str,
+            role_arn: &str,
+            kind: &str,
+        ) -> Result<CreateStateMachineResponse, BackendError> {
+            self.fake
+                .create_state_machine(region, name, definition, role_arn, kind)
+                .await
+        }
+
+        async fn update_state_machine(
+            &self,
+            region: &str,
+            arn: &str,
+            definition: &str,
+        ) -> Result<UpdateStateMachineResponse, BackendError> {
+            self.fake
+                .update_state_machine(region, arn, definition)
+                .await
+        }
+
+        async fn delete_state_machine(&self, region: &str, arn: &str) -> Result<(), BackendError> {
+            self.fake.delete_state_machine(region, arn).await
+        }
+    }
+
+    fn hanging() -> Arc<Hanging> {
+        Arc::new(Hanging {
+            started: AtomicUsize::new(0),
+            fake: FakeBackend::new(),
+        })
+    }
+
+    #[actix_web::test]
+    async fn hung_calls_time_out() {
+        let bounded = Bounded::new(hanging(), 4, Duration::from_millis(20));
+        match bounded.list_state_machines("us-east-1").await {
+            Err(BackendError::Timeout(timeout)) => {
+                assert_eq!(timeout, Duration::from_millis(20))
+            }
+            _ => panic!("expected a timeout"),
+        }
+    }
+
+    #[actix_web::test]
+    async fn calls_past_the_cap_wait_and_cancelled_calls_free_their_slot() {
+        let inner = hanging();
+        let bounded = Bounded::new(inner.clone(), 1, Duration::from_secs(5));
+
+        // Dropping a pending call (what actix does when the client goes away)
+        // must release its slot.
+        let first = tokio::time::timeout(
+            Duration::from_millis(20),
+            bounded.list_state_machines("us-east-1"),
+        )
+        .await;
+        assert!(first.is_err());
+        assert_eq!(inner.started.load(Ordering::SeqCst), 1);
+
+        let second = tokio::time::timeout(
+            Duration::from_millis(20),
+            bounded.list_state_machines("us-east-1"),
+        )
+        .await;
+        assert!(second.is_err());
+        assert_eq!(inner.started.load(Ordering::SeqCst), 2);
+
+        // While a call holds the only slot, others queue instead of running.
+        let holder = bounded.list_state_machines("us-east-1");
+        let waiter = bounded.list_state_machines("us-east-1");
+        let _ = tokio::time::timeout(Duration::from_millis(20), async {
+            tokio::join!(holder, waiter)
+        })
+        .await;
+        assert_eq!(inner.started.load(Ordering::SeqCst), 3);
+    }
+}
@@ -0,0 +1,319 @@
+use std::str;
+
+use async_trait::async_trait;
+use serde::de::DeserializeOwned;
+use tokio::process::Command;
+
+use super::{BackendError, StepFunctions};
+use crate::history::{EventResponse, HistoryQuery};
+use crate::model::{
+    CreateStateMachineResponse, ExecutionDescription, ExecutionsQuery, ExecutionsResponse,
+    StartExecutionResponse, StateMachine, StateMachineDescriptor, StateMachineResponse,
+    StopExecutionResponse, UpdateStateMachineResponse,
+};
+
+/// Shells out to `aws stepfunctions`, for machines where the CLI's own
+/// configuration (profiles, aws-vault) is the source of truth.
+pub struct CliBackend {
+    endpoint: String,
+}
+
+impl CliBackend {
+    pub fn new(endpoint: impl Into<String>) -> Self {
+        CliBackend {
+            endpoint: endpoint.into(),
+        }
+    }
+
+    /// Builds the invocation as an argument vector; nothing passes through a shell.
+    /// Dropping the pending output (request cancelled or timed out) kills the process.
+    fn command(&self, region: &str, args: &[&str]) -> Command {
+        let mut command = Command::new("aws");
+        command
+            .kill_on_drop(true)
+            .arg("stepfunctions")
+            .args(args)
+            .args(["--endpoint-url", &self.endpoint, "--region", region]);
+        command
+    }
+
+    async fn run(&self, region: &str, args: &[&str]) -> Result<String, BackendError> {
+        let output = self.command(region, args).output().await.map_err(|e| {
+            BackendError::Unavailable(format!("failed to execute the aws CLI: {e}"))
+        })?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(match parse_cli_error(&stderr) {
+                Some((kind, message)) => BackendError::Service { kind, message },
+                None => BackendError::Cli {
+                    status: output.status.to_string(),
+                    stderr: stderr.trim().to_string(),
+                },
+            });
+        }
+
+        str::from_utf8(&output.stdout)
+            .map(str::to_string)
+            .map_err(|e| BackendError::Parse(format!("output convert failed due to: {e}")))
+    }
+
+    async fn run_json<O: DeserializeOwned>(
+        &self,
+        region: &str,
+        args: &[&str],
+    ) -> Result<O, BackendError> {
+        let output = self.run(region, args).await?;
+        serde_json::from_str(&output).map_err(|e| BackendError::Parse(e.to_string()))
+    }
+}
+
+/// Pulls the error code and message out of the CLI's
+/// `An error occurred (Code) when calling the Operation operation: message` line.
+fn parse_cli_error(stderr: &str) -> Option<(String, String)> {
+    let line = stderr
+        .lines()
+        .find(|line| line.starts_with("An error occurred ("))?;
+    let rest = &line["An error occurred (".len()..];
+    let (kind, rest) = rest.split_once(')')?;
+    let message = rest
+        .split_once(": ")
+        .map(|(_, message)| message)
+        .unwrap_or_default();
+    Some((kind.to_string(), message.to_string()))
+}
+
+#[async_trait]
+impl StepFunctions for CliBackend {
+    async fn list_state_machines(
+        &self,
+        region: &str,
+    ) -> Result<StateMachineResponse, BackendError> {
+        self.run_json(region, &["list-state-machines"]).await
+    }
+
+    async fn describe_state_machine(
+        &self,
+        region: &str,
+        arn: &str,
+    ) -> Result<StateMachine, BackendError> {
+        self.run_json(
+            region,
+            &["describe-state-machine", "--state-machine-arn", arn],
+        )
+        .await
+    }
+
+    async fn list_executions(
+        &self,
+        region: &str,
+        arn: &str,
+        query: &ExecutionsQuery,
+    ) -> Result<ExecutionsResponse, BackendError> {
+        // `--no-paginate` makes one call and passes the page options through,
+        // so `nextToken` in the output is the service's own token.
+        let mut args = vec![
+            String::from("list-executions"),
+            String::from("--no-paginate"),
+            String::from("--state-machine-arn"),
+            arn.to_string(),
+        ];
+        if let Some(status) = &query.status {
+            args.push(format!("--status-filter={status}"));
+        }
+        if let Some(limit) = query.limit {
+            args.push(format!("--max-results={limit}"));
+        }
+        if let Some(token) = &query.next_token {
+            args.push(format!("--next-token={token}"));
+        }
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        self.run_json(region, &args).await
+    }
+
+    async fn get_execution_history(
+        &self,
+        region: &str,
+        arn: &str,
+        query: &HistoryQuery,
+    ) -> Result<EventResponse, BackendError> {
+        let mut args = vec![
+            String::from("get-execution-history"),
+            String::from("--no-paginate"),
+            String::from("--execution-arn"),
+            arn.to_string(),
+            String::from(if query.reverse_order {
+                "--reverse-order"
+            } else {
+                "--no-reverse-order"
+            }),
+            String::from(if query.include_execution_data {
+                "--include-execution-data"
+            } else {
+                "--no-include-execution-data"
+            }),
+        ];
+        if let Some(max_results) = query.max_results {
+            args.push(format!("--max-results={max_results}"));
+        }
+        if let Some(token) = &query.next_token {
+            args.push(format!("--next-token={token}"));
+        }
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        self.run_json(region, &args).await
+    }
+
+    async fn describe_execution(
+        &self,
+        region: &str,
+        arn: &str,
+    ) -> Result<ExecutionDescription, BackendError> {
+        self.run_json(region, &["describe-execution", "--execution-arn", arn])
+            .await
+    }
+
+    async fn describe_state_machine_for_execution(
+        &self,
+        region: &str,
+        arn: &str,
+    ) -> Result<StateMachineDescriptor, BackendError> {
+        self.run_json(
+            region,
+            &[
+                "describe-state-machine-for-execution",
+                "--execution-arn",
+                arn,
+            ],
+        )
+        .await
+    }
+
+    async fn start_execution(
+        &self,
+        region: &str,
+        arn: &str,
+        name: Option<&str>,
+        input: &str,
+    ) -> Result<StartExecutionResponse, BackendError> {
+        // Input and name come from the request body; see `stop_execution`.
+        let input = format!("--input={input}");
+        let name = name.map(|name| format!("--name={name}"));
+        let mut args = vec!["start-execution", "--state-machine-arn", arn, &input];
+        args.extend(name.as_deref());
+        self.run_json(region, &args).await
+    }
+
+    async fn stop_execution(
+        &self,
+        region: &str,
+        arn: &str,
+        error: &str,
+        cause: &str,
+    ) -> Result<StopExecutionResponse, BackendError> {
+        // Error and cause come from the request body; the `=` form keeps a
+        // value starting with `--` from being read as an option.
+        let cause = format!("--cause={cause}");
+        let error = format!("--error={error}");
+        self.run_json(
+            region,
+            &["stop-execution", "--execution-arn", arn, &cause, &error],
+        )
+        .await
+    }
+
+    async fn create_state_machine(
+        &self,
+        region: &str,
+        name: &str,
+        definition: &str,
+        role_arn: &str,
+        kind: &str,
+    ) -> Result<CreateStateMachineResponse, BackendError> {
+        self.run_json(
+            region,
+            &[
+                "create-state-machine",
+                "--name",
+                name,
+                "--definition",
+                definition,
+                "--role-arn",
+                role_arn,
+                "--type",
+                kind,
+            ],
+        )
+        .await
+    }
+
+    async fn update_state_machine(
+        &self,
+        region: &str,
+        arn: &str,
+        definition: &str,
+    ) -> Result<UpdateStateMachineResponse, BackendError> {
+        self.run_json(
+            region,
+            &[
+                "update-state-machine",
+                "--state-machine-arn",
+                arn,
+                "--definition",
+                definition,
+            ],
+        )
+        .await
+    }
+
+    async fn delete_state_machine(&self, region: &str, arn: &str) -> Result<(), BackendError> {
+        self.run(
+            region,
+            &["delete-state-machine", "--state-machine-arn", arn],
+        )
+        .await
+        .map(|_| ())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_cli_error_lines() {
+        let stderr = "\nAn error occurred (StateMachineDoesNotExist) when calling the DescribeStateMachine operation: State Machine Does Not Exist: 'arn:aws:states:us-east-1:123456789012:stateMachine:gone'\n";
+        let (kind, message) = parse_cli_error(stderr).unwrap();
+        assert_eq!(kind, "StateMachineDoesNotExist");
+        assert_eq!(
+            message,
+            "State Machine Does Not Exist: 'arn:aws:states:us-east-1:123456789012:stateMachine:gone'"
+        );
+
+        assert!(parse_cli_error("aws: command not found").is_none());
+    }
+
+    #[test]
+    fn arguments_are_passed_without_a_shell() {
+        let backend = CliBackend::new("http://localhost:8083");
+        let command = backend.command(
+            "us-east-1",
+            &["stop-execution", "--cause", "manual stop; rm -rf ~"],
+        );
+
+        assert_eq!(command.as_std().get_program(), "aws");
+        let args: Vec<_> = command.as_std().get_args().collect();
+        assert_eq!(
+            args,
+            vec![
+                "stepfunctions",
+                "stop-execution",
+                "--cause",
+                "manual stop; rm -rf ~",
+                "--endpoint-url",
+                "http://localhost:8083",
+                "--region",
+                "us-east-1",
+            ]
+        );
+    }
+}
@@ -0,0 +1,373 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+
+use super::{BackendError, StepFunctions};
+use crate::history::{
+    Event, EventDetails, EventResponse, ExecutionStartedEventDetails, HistoryQuery,
+};
+use crate::model::{
+    CreateStateMachineResponse, ExecutionDescription, Executions, ExecutionsQuery,
+    ExecutionsResponse, StartExecutionResponse, StateMachine, StateMachineDescriptor,
+    StateMachineResponse, StopExecutionResponse, UpdateStateMachineResponse,
+};
+use crate::timestamp::Timestamp;
+
+/// In-memory Step Functions for handler tests; regions are ignored.
+#[derive(Default)]
+pub struct FakeBackend {
+    state: Mutex<FakeState>,
+}
+
+#[derive(Default)]
+struct FakeState {
+    state_machines: Vec<StateMachine>,
+    definitions: HashMap<String, String>,
+    executions: Vec<Executions>,
+    histories: HashMap<String, Vec<Event>>,
+    /// Error and cause of executions stopped through `stop_execution`.
+    stop_reasons: HashMap<String, (String, String)>,
+    started: usize,
+}
+
+fn state_machine_does_not_exist(arn: &str) -> BackendError {
+    BackendError::Service {
+        kind: String::from("StateMachineDoesNotExist"),
+        message: format!("State Machine Does Not Exist: '{arn}'"),
+    }
+}
+
+fn execution_does_not_exist(arn: &str) -> BackendError {
+    BackendError::Service {
+        kind: String::from("ExecutionDoesNotExist"),
+        message: format!("Execution Does Not Exist: '{arn}'"),
+    }
+}
+
+/// One page of `items`; tokens are the index of the next item.
+fn page<T: Clone>(
+    items: Vec<&T>,
+    limit: Option<u32>,
+    token: Option<&str>,
+) -> Result<(Vec<T>, Option<String>), BackendError> {
+    let start: usize = match token {
+        Some(token) => token.parse().map_err(|_| BackendError::Service {
+            kind: String::from("InvalidToken"),
+            message: format!("Invalid Token: '{token}'"),
+        })?,
+        None => 0,
+    };
+    let limit = limit.map_or(usize::MAX, |limit| limit as usize);
+    let page: Vec<T> = items
+        .iter()
+        .skip(start)
+        .take(limit)
+        .cloned()
+        .cloned()
+        .collect();
+    let end = start + page.len();
+    Ok((page, (end < items.len()).then(|| end.to_string())))
+}
+
+impl FakeBackend {
+    pub fn new() -> Self {
+        FakeBackend::default()
+    }
+
+    pub fn with_state_machine(self, machine: StateMachine, definition: &str) -> Self {
+        {
+            let mut state = self.state.lock().unwrap();
+            state
+                .definitions
+                .insert(machine.state_machine_arn.clone(), definition.to_string());
+            state.state_machines.push(machine);
+        }
+        self
+    }
+
+    pub fn with_execution(self, execution: Executions, history: Vec<Event>) -> Self {
+        {
+            let mut state = self.state.lock().unwrap();
+            state
+                .histories
+                .insert(execution.execution_arn.clone(), history);
+            state.executions.push(execution);
+        }
+        self
+    }
+}
+
+#[async_trait]
+impl StepFunctions for FakeBackend {
+    async fn list_state_machines(
+        &self,
+        _region: &str,
+    ) -> Result<StateMachineResponse, BackendError> {
+        let state = self.state.lock().unwrap();
+        Ok(StateMachineResponse {
+            state_machines: state.state_machines.clone(),
+        })
+    }
+
+    async fn describe_state_machine(
+        &self,
+        _region: &str,
+        arn: &str,
+    ) -> Result<StateMachine, BackendError> {
+        let state = self.state.lock().unwrap();
+        let mut machine = state
+            .state_machines
+            .iter()
+            .find(|machine| machine.state_machine_arn == arn)
+            .cloned()
+            .ok_or_else(|| state_machine_does_not_exist(arn))?;
+        machine.definition = state.definitions.get(arn).cloned();
+        Ok(machine)
+    }
+
+    async fn list_executions(
+        &self,
+        _region: &str,
+        arn: &str,
+        query: &ExecutionsQuery,
+    ) -> Result<ExecutionsResponse, BackendError> {
+        let state = self.state.lock().unwrap();
+        if !state.definitions.contains_key(arn) {
+            return Err(state_machine_does_not_exist(arn));
+        }
+        let matching: Vec<&Executions> = state
+            .executions
+            .iter()
+            .filter(|execution| execution.state_machine_arn == arn)
+            .filter(|execution| {
+                query
+                    .status
+                    .as_ref()
+                    .is_none_or(|status| &execution.status == status)
+            })
+            .collect();
+        let (executions, next_token) = page(matching, query.limit, query.next_token.as_deref())?;
+        Ok(ExecutionsResponse {
+            executions,
+            next_token,
+        })
+    }
+
+    async fn get_execution_history(
+        &self,
+        _region: &str,
+        arn: &str,
+        query: &HistoryQuery,
+    ) -> Result<EventResponse, BackendError> {
+        let state = self.state.lock().unwrap();
+        let history = state
+            .histories
+            .get(arn)
+            .ok_or_else(|| execution_does_not_exist(arn))?;
+        let mut events: Vec<&Event> = history.iter().collect();
+        if query.reverse_order {
+            events.reverse();
+        }
+        let (events, next_token) = page(events, query.max_results, query.next_token.as_deref())?;
+        Ok(EventResponse { events, next_token })
+    }
+
+    async fn describe_execution(
+        &self,
+        _region: &str,
+        arn: &str,
+    ) -> Result<ExecutionDescription, BackendError> {
+        let state = self.state.lock().unwrap();
+        let execution = state
+            .executions
+            .iter()
+            .find(|execution| execution.execution_arn == arn)
+            .ok_or_else(|| execution_does_not_exist(arn))?;
+        let input = state.histories[arn]
+            .iter()
+            .find_map(|event| event.execution_started())
+            .map(|details| details.input.clone());
+        let (error, cause) = state.stop_reasons.get(arn).cloned().unzip();
+        Ok(ExecutionDescription {
+            execution_arn: execution.execution_arn.clone(),
+            state_machine_arn: execution.state_machine_arn.clone(),
+            name: execution.name.clone(),
+            status: execution.status.clone(),
+            start_date: execution.start_date,
+            stop_date: execution.stop_date,
+            input,
+            output: None,
+            error,
+            cause,
+            duration_millis: None,
+        })
+    }
+
+    async fn describe_state_machine_for_execution(
+        &self,
+        _region: &str,
+        arn: &str,
+    ) -> Result<StateMachineDescriptor, BackendError> {
+        let state = self.state.lock().unwrap();
+        let execution = state
+            .executions
+            .iter()
+            .find(|execution| execution.execution_arn == arn)
+            .ok_or_else(|| execution_does_not_exist(arn))?;
+        let machine = state
+            .state_machines
+            .iter()
+            .find(|machine| machine.state_machine_arn == execution.state_machine_arn)
+            .ok_or_else(|| state_machine_does_not_exist(&execution.state_machine_arn))?;
+        Ok(StateMachineDescriptor {
+            state_machine_arn: machine.state_machine_arn.clone(),
+            name: machine.name.clone(),
+            definition: state.definitions[&machine.state_machine_arn].clone(),
+        })
+    }
+
+    async fn start_execution(
+        &self,
+        _region: &str,
+        arn: &str,
+        name: Option<&str>,
+        input: &str,
+    ) -> Result<StartExecutionResponse, BackendError> {
+        let mut state = self.state.lock().unwrap();
+        if !state.definitions.contains_key(arn) {
+            return Err(state_machine_does_not_exist(arn));
+        }
+        state.started += 1;
+        let name = name
+            .map(str::to_string)
+            .unwrap_or_else(|| format!("execution-{}", state.started));
+        let execution_arn = format!(
+            "{}:{}",
+            arn.replacen(":stateMachine:", ":execution:", 1),
+            name
+        );
+        if state.histories.contains_key(&execution_arn) {
+            return Err(BackendError::Service {
+                kind: String::from("ExecutionAlreadyExists"),
+                message: format!("Execution Already Exists: '{execution_arn}'"),
+            });
+        }
+
+        let start_date = Timestamp::now();
+        state.histories.insert(
+            execution_arn.clone(),
+            vec![Event {
+                timestamp: start_date,
+                id: 1,
+                previous_event_id: Some(0),
+                details: EventDetails::ExecutionStarted {
+                    details: ExecutionStartedEventDetails {
+                        input: input.to_string(),
+                        role_arn: String::from("arn:aws:iam::123456789012:role/DummyRole"),
+                        input_details: None,
+                        state_machine_alias_arn: None,
+                        state_machine_version_arn: None,
+                    },
+                },
+            }],
+        );
+        state.executions.push(Executions {
+            execution_arn: execution_arn.clone(),
+            state_machine_arn: arn.to_string(),
+            name,
+            status: String::from("RUNNING"),
+            start_date,
+            stop_date: None,
+            duration_millis: None,
+        });
+        Ok(StartExecutionResponse {
+            execution_arn,
+            start_date,
+        })
+    }
+
+    async fn stop_execution(
+        &self,
+        _region: &str,
+        arn: &str,
+        error: &str,
+        cause: &str,
+    ) -> Result<StopExecutionResponse, BackendError> {
+        let state = &mut *self.state.lock().unwrap();
+        let execution = state
+            .executions
+            .iter_mut()
+            .find(|execution| execution.execution_arn == arn)
+            .ok_or_else(|| execution_does_not_exist(arn))?;
+        if execution.status == "RUNNING" {
+            execution.status = String::from("ABORTED");
+            execution.stop_date = Some(Timestamp::now());
+            state
+                .stop_reasons
+                .insert(arn.to_string(), (error.to_string(), cause.to_string()));
+        }
+        Ok(StopExecutionResponse {
+            stop_date: execution.stop_date.unwrap_or_else(Timestamp::now),
+        })
+    }
+
+    async fn create_state_machine(
+        &self,
+        _region: &str,
+        name: &str,
+        definition: &str,
+        _role_arn: &str,
+        kind: &str,
+    ) -> Result<CreateStateMachineResponse, BackendError> {
+        let mut state = self.state.lock().unwrap();
+        let arn = format!("arn:aws:states:us-east-1:123456789012:stateMachine:{name}");
+        if state.definitions.contains_key(&arn) {
+            return Err(BackendError::Service {
+                kind: String::from("StateMachineAlreadyExists"),
+                message: format!("State Machine Already Exists: '{arn}'"),
+            });
+        }
+        let creation_date = Timestamp::now();
+        state
+            .definitions
+            .insert(arn.clone(), definition.to_string());
+        state.state_machines.push(StateMachine {
+            name: name.to_string(),
+            state_machine_arn: arn.clone(),
+            kind: kind.to_string(),
+            creation_date,
+            definition: None,
+        });
+        Ok(CreateStateMachineResponse {
+            state_machine_arn: arn,
+            creation_date,
+        })
+    }
+
+    async fn update_state_machine(
+        &self,
+        _region: &str,
+        arn: &str,
+        definition: &str,
+    ) -> Result<UpdateStateMachineResponse, BackendError> {
+        let mut state = self.state.lock().unwrap();
+        let current = state
+            .definitions
+            .get_mut(arn)
+            .ok_or_else(|| state_machine_does_not_exist(arn))?;
+        *current = definition.to_string();
+        Ok(UpdateStateMachineResponse {
+            update_date: Timestamp::now(),
+        })
+    }
+
+    async fn delete_state_machine(&self, _region: &str, arn: &str) -> Result<(), BackendError> {
+        let mut state = self.state.lock().unwrap();
+        state
+            .state_machines
+            .retain(|machine| machine.state_machine_arn != arn);
+        state.definitions.remove(arn);
+        Ok(())
+    }
+}
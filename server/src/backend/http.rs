@@ -0,0 +1,425 @@
+use async_trait::async_trait;
+use chrono::Utc;
+use reqwest::header::{AUTHORIZATION, CONTENT_TYPE};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use super::{BackendError, StepFunctions};
+use crate::history::{EventResponse, HistoryQuery};
+use crate::model::{
+    CreateStateMachineResponse, ExecutionDescription, ExecutionsQuery, ExecutionsResponse,
+    StartExecutionResponse, StateMachine, StateMachineDescriptor, StateMachineResponse,
+    StopExecutionResponse, UpdateStateMachineResponse,
+};
+
+const CONTENT_TYPE_AMZ_JSON: &str = "application/x-amz-json-1.0";
+const TARGET_PREFIX: &str = "AWSStepFunctions";
+
+/// Talks the AWS JSON 1.0 protocol to a Step Functions endpoint.
+///
+/// Local emulators (Step Functions Local, LocalStack) read the region from the
+/// credential scope but never verify the signature, so requests carry a
+/// well-formed but unsigned `Authorization` header.
+pub struct HttpBackend {
+    http: reqwest::Client,
+    endpoint: String,
+}
+
+#[derive(Deserialize)]
+struct AwsErrorBody {
+    #[serde(rename = "__type")]
+    kind: Option<String>,
+    #[serde(alias = "Message")]
+    message: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ListStateMachinesPage {
+    #[serde(rename = "stateMachines")]
+    state_machines: Vec<StateMachine>,
+    #[serde(rename = "nextToken")]
+    next_token: Option<String>,
+}
+
+impl HttpBackend {
+    pub fn new(endpoint: impl Into<String>) -> Self {
+        HttpBackend {
+            http: reqwest::Client::new(),
+            endpoint: endpoint.into(),
+        }
+    }
+
+    async fn call<O: DeserializeOwned>(
+        &self,
+        region: &str,
+        operation: &str,
+        input: Value,
+    ) -> Result<O, BackendError> {
+        let now = Utc::now();
+        let access_key =
+            std::env::var("AWS_ACCESS_KEY_ID").unwrap_or_else(|_| String::from("local"));
+        let authorization = format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}/{}/states/aws4_request, SignedHeaders=content-type;host;x-amz-date;x-amz-target, Signature=unsigned",
+            access_key,
+            now.format("%Y%m%d"),
+            region
+        );
+
+        let response = self
+            .http
+            .post(&self.endpoint)
+            .header(CONTENT_TYPE, CONTENT_TYPE_AMZ_JSON)
+            .header("X-Amz-Target", format!("{TARGET_PREFIX}.{operation}"))
+            .header("X-Amz-Date", now.format("%Y%m%dT%H%M%SZ").to_string())
+            .header(AUTHORIZATION, authorization)
+            .body(input.to_string())
+            .send()
+            .await
+            .map_err(|e| BackendError::Unavailable(e.to_string()))?;
+
+        let status = response.status();
+        let body = response
+            .bytes()
+            .await
+            .map_err(|e| BackendError::Unavailable(e.to_string()))?;
+
+        if !status.is_success() {
+            let error = serde_json::from_slice::<AwsErrorBody>(&body).ok();
+            let kind = error
+                .as_ref()
+                .and_then(|e| e.kind.as_deref())
+                .map(|kind| kind.rsplit('#').next().unwrap_or(kind).to_string())
+                .unwrap_or_else(|| String::from("UnknownError"));
+            let message = error
+                .and_then(|e| e.message)
+                .unwrap_or_else(|| String::from_utf8_lossy(&body).into_owned());
+            return Err(BackendError::Service { kind, message });
+        }
+
+        serde_json::from_slice(&body).map_err(|e| BackendError::Parse(e.to_string()))
+    }
+}
+
+#[async_trait]
+impl StepFunctions for HttpBackend {
+    /// Lists every state machine in the region, following `nextToken` the way
+    /// the CLI's auto-pagination does.
+    async fn list_state_machines(
+        &self,
+        region: &str,
+    ) -> Result<StateMachineResponse, BackendError> {
+        let mut state_machines = Vec::new();
+        let mut next_token: Option<String> = None;
+        loop {
+            let input = match &next_token {
+                Some(token) => json!({ "nextToken": token }),
+                None => json!({}),
+            };
+            let page: ListStateMachinesPage = self.call(region, "ListStateMachines", input).await?;
+            state_machines.extend(page.state_machines);
+            next_token = page.next_token;
+            if next_token.is_none() {
+                break;
+            }
+        }
+        Ok(StateMachineResponse { state_machines })
+    }
+
+    async fn describe_state_machine(
+        &self,
+        region: &str,
+        arn: &str,
+    ) -> Result<StateMachine, BackendError> {
+        self.call(
+            region,
+            "DescribeStateMachine",
+            json!({ "stateMachineArn": arn }),
+        )
+        .await
+    }
+
+    async fn list_executions(
+        &self,
+        region: &str,
+        arn: &str,
+        query: &ExecutionsQuery,
+    ) -> Result<ExecutionsResponse, BackendError> {
+        let mut request = json!({ "stateMachineArn": arn });
+        if let Some(status) = &query.status {
+            request["statusFilter"] = json!(status);
+        }
+        if let Some(limit) = query.limit {
+            request["maxResults"] = json!(limit);
+        }
+        if let Some(token) = &query.next_token {
+            request["nextToken"] = json!(token);
+        }
+        self.call(region, "ListExecutions", request).await
+    }
+
+    async fn get_execution_history(
+        &self,
+        region: &str,
+        arn: &str,
+        query: &HistoryQuery,
+    ) -> Result<EventResponse, BackendError> {
+        let mut request = json!({
+            "executionArn": arn,
+            "reverseOrder": query.reverse_order,
+            "includeExecutionData": query.include_execution_data,
+        });
+        if let Some(max_results) = query.max_results {
+            request["maxResults"] = json!(max_results);
+        }
+        if let Some(token) = &query.next_token {
+            request["nextToken"] = json!(token);
+        }
+        self.call(region, "GetExecutionHistory", request).await
+    }
+
+    async fn describe_execution(
+        &self,
+        region: &str,
+        arn: &str,
+    ) -> Result<ExecutionDescription, BackendError> {
+        self.call(region, "DescribeExecution", json!({ "executionArn": arn }))
+            .await
+    }
+
+    async fn describe_state_machine_for_execution(
+        &self,
+        region: &str,
+        arn: &str,
+    ) -> Result<StateMachineDescriptor, BackendError> {
+        self.call(
+            region,
+            "DescribeStateMachineForExecution",
+            json!({ "executionArn": arn }),
+        )
+        .await
+    }
+
+    async fn start_execution(
+        &self,
+        region: &str,
+        arn: &str,
+        name: Option<&str>,
+        input: &str,
+    ) -> Result<StartExecutionResponse, BackendError> {
+        let mut request = json!({ "stateMachineArn": arn, "input": input });
+        if let Some(name) = name {
+            request["name"] = json!(name);
+        }
+        self.call(region, "StartExecution", request).await
+    }
+
+    async fn stop_execution(
+        &self,
+        region: &str,
+        arn: &str,
+        error: &str,
+        cause: &str,
+    ) -> Result<StopExecutionResponse, BackendError> {
+        self.call(
+            region,
+            "StopExecution",
+            json!({ "executionArn": arn, "error": error, "cause": cause }),
+        )
+        .await
+    }
+
+    async fn create_state_machine(
+        &self,
+        region: &str,
+        name: &str,
+        definition: &str,
+        role_arn: &str,
+        kind: &str,
+    ) -> Result<CreateStateMachineResponse, BackendError> {
+        self.call(
+            region,
+            "CreateStateMachine",
+            json!({
+                "name": name,
+                "definition": definition,
+                "roleArn": role_arn,
+                "type": kind,
+            }),
+        )
+        .await
+    }
+
+    async fn update_state_machine(
+        &self,
+        region: &str,
+        arn: &str,
+        definition: &str,
+    ) -> Result<UpdateStateMachineResponse, BackendError> {
+        self.call(
+            region,
+            "UpdateStateMachine",
+            json!({ "stateMachineArn": arn, "definition": definition }),
+        )
+        .await
+    }
+
+    async fn delete_state_machine(&self, region: &str, arn: &str) -> Result<(), BackendError> {
+        self.call::<Value>(
+            region,
+            "DeleteStateMachine",
+            json!({ "stateMachineArn": arn }),
+        )
+        .await
+        .map(|_| ())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{dev::ServerHandle, web, App, HttpRequest, HttpResponse, HttpServer};
+
+    /// Minimal stand-in for Step Functions Local: answers by `X-Amz-Target`.
+    async fn stand_in(req: HttpRequest, body: web::Bytes) -> HttpResponse {
+        let target = req
+            .headers()
+            .get("X-Amz-Target")
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or_default()
+            .to_string();
+        let input: Value = serde_json::from_slice(&body).unwrap_or(Value::Null);
+        let content_type = req
+            .headers()
+            .get("Content-Type")
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or_default();
+        assert_eq!(content_type, CONTENT_TYPE_AMZ_JSON);
+
+        match target.as_str() {
+            "AWSStepFunctions.ListStateMachines" => {
+                if input.get("nextToken").is_none() {
+                    HttpResponse::Ok().json(json!({
+                        "stateMachines": [{
+                            "stateMachineArn": "arn:aws:states:us-east-1:123456789012:stateMachine:first",
+                            "name": "first",
+                            "type": "STANDARD",
+                            "creationDate": 1699612567.763
+                        }],
+                        "nextToken": "page-2"
+                    }))
+                } else {
+                    HttpResponse::Ok().json(json!({
+                        "stateMachines": [{
+                            "stateMachineArn": "arn:aws:states:us-east-1:123456789012:stateMachine:second",
+                            "name": "second",
+                            "type": "EXPRESS",
+                            "creationDate": 1699612600.0
+                        }]
+                    }))
+                }
+            }
+            "AWSStepFunctions.ListExecutions" => HttpResponse::Ok().json(json!({
+                "executions": [{
+                    "executionArn": "arn:aws:states:us-east-1:123456789012:execution:first:run",
+                    "stateMachineArn": input["stateMachineArn"],
+                    "name": "run",
+                    "status": "SUCCEEDED",
+                    "startDate": 1699612567.763,
+                    "stopDate": 1699612568.1
+                }]
+            })),
+            "AWSStepFunctions.StopExecution" => {
+                assert_eq!(input["error"], "manualstop");
+                HttpResponse::Ok().json(json!({ "stopDate": 1699612568.1 }))
+            }
+            _ => HttpResponse::BadRequest().json(json!({
+                "__type": "com.amazonaws.swf.service.v2.model#StateMachineDoesNotExist",
+                "message": "State Machine Does Not Exist"
+            })),
+        }
+    }
+
+    fn start_stand_in() -> (String, ServerHandle) {
+        let server = HttpServer::new(|| App::new().default_service(web::to(stand_in)))
+            .workers(1)
+            .bind(("127.0.0.1", 0))
+            .unwrap();
+        let endpoint = format!("http://{}", server.addrs()[0]);
+        let server = server.run();
+        let handle = server.handle();
+        actix_web::rt::spawn(server);
+        (endpoint, handle)
+    }
+
+    #[actix_web::test]
+    async fn list_state_machines_follows_next_token() {
+        let (endpoint, handle) = start_stand_in();
+        let client = HttpBackend::new(endpoint);
+
+        let response = client.list_state_machines("us-east-1").await.unwrap();
+        let names: Vec<&str> = response
+            .state_machines
+            .iter()
+            .map(|m| m.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["first", "second"]);
+
+        handle.stop(false).await;
+    }
+
+    #[actix_web::test]
+    async fn list_executions_accepts_epoch_dates() {
+        let (endpoint, handle) = start_stand_in();
+        let client = HttpBackend::new(endpoint);
+
+        let response = client
+            .list_executions(
+                "us-east-1",
+                "arn:aws:states:us-east-1:123456789012:stateMachine:first",
+                &ExecutionsQuery::default(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.executions.len(), 1);
+        assert_eq!(response.executions[0].status, "SUCCEEDED");
+        assert_eq!(
+            response.executions[0].start_date.to_string(),
+            "2023-11-10T10:36:07.763Z"
+        );
+        assert_eq!(response.executions[0].duration_millis(), Some(337));
+
+        client
+            .stop_execution(
+                "us-east-1",
+                &response.executions[0].execution_arn,
+                "manualstop",
+                "test",
+            )
+            .await
+            .unwrap();
+
+        handle.stop(false).await;
+    }
+
+    #[actix_web::test]
+    async fn service_errors_carry_the_aws_error_type() {
+        let (endpoint, handle) = start_stand_in();
+        let client = HttpBackend::new(endpoint);
+
+        match client
+            .describe_state_machine(
+                "us-east-1",
+                "arn:aws:states:us-east-1:123456789012:stateMachine:missing",
+            )
+            .await
+        {
+            Err(BackendError::Service { kind, .. }) => {
+                assert_eq!(kind, "StateMachineDoesNotExist");
+            }
+            _ => panic!("expected a service error"),
+        }
+
+        handle.stop(false).await;
+    }
+}
@@ -0,0 +1,147 @@
+use std::fmt;
+use std::time::Duration;
+
+use async_trait::async_trait;
+
+use crate::history::{EventResponse, HistoryQuery};
+use crate::model::{
+    CreateStateMachineResponse, ExecutionDescription, ExecutionsQuery, ExecutionsResponse,
+    StartExecutionResponse, StateMachine, StateMachineDescriptor, StateMachineResponse,
+    StopExecutionResponse, UpdateStateMachineResponse,
+};
+
+mod bounded;
+mod cli;
+#[cfg(test)]
+mod fake;
+mod http;
+
+pub use bounded::Bounded;
+pub use cli::CliBackend;
+#[cfg(test)]
+pub use fake::FakeBackend;
+pub use http::HttpBackend;
+
+/// The Step Functions operations the routes rely on.
+///
+/// Handlers only ever see `dyn StepFunctions`, so an emulator with a different
+/// transport only needs another implementation, not route changes.
+#[async_trait]
+pub trait StepFunctions: Send + Sync {
+    async fn list_state_machines(&self, region: &str)
+        -> Result<StateMachineResponse, BackendError>;
+
+    async fn describe_state_machine(
+        &self,
+        region: &str,
+        arn: &str,
+    ) -> Result<StateMachine, BackendError>;
+
+    async fn list_executions(
+        &self,
+        region: &str,
+        arn: &str,
+        query: &ExecutionsQuery,
+    ) -> Result<ExecutionsResponse, BackendError>;
+
+    async fn get_execution_history(
+        &self,
+        region: &str,
+        arn: &str,
+        query: &HistoryQuery,
+    ) -> Result<EventResponse, BackendError>;
+
+    async fn describe_execution(
+        &self,
+        region: &str,
+        arn: &str,
+    ) -> Result<ExecutionDescription, BackendError>;
+
+    async fn describe_state_machine_for_execution(
+        &self,
+        region: &str,
+        arn: &str,
+    ) -> Result<StateMachineDescriptor, BackendError>;
+
+    /// `input` is the JSON document handed to the first state.
+    async fn start_execution(
+        &self,
+        region: &str,
+        arn: &str,
+        name: Option<&str>,
+        input: &str,
+    ) -> Result<StartExecutionResponse, BackendError>;
+
+    async fn stop_execution(
+        &self,
+        region: &str,
+        arn: &str,
+        error: &str,
+        cause: &str,
+    ) -> Result<StopExecutionResponse, BackendError>;
+
+    async fn create_state_machine(
+        &self,
+        region: &str,
+        name: &str,
+        definition: &str,
+        role_arn: &str,
+        kind: &str,
+    ) -> Result<CreateStateMachineResponse, BackendError>;
+
+    async fn update_state_machine(
+        &self,
+        region: &str,
+        arn: &str,
+        definition: &str,
+    ) -> Result<UpdateStateMachineResponse, BackendError>;
+
+    async fn delete_state_machine(&self, region: &str, arn: &str) -> Result<(), BackendError>;
+}
+
+#[derive(Debug)]
+pub enum BackendError {
+    /// The backend could not be reached: endpoint down, CLI missing, process killed.
+    Unavailable(String),
+    /// Step Functions rejected the call; `kind` is the AWS error code.
+    Service { kind: String, message: String },
+    /// The backend answered with something we could not interpret.
+    Parse(String),
+    /// The call did not finish within the configured deadline.
+    Timeout(Duration),
+    /// The CLI failed without an AWS error we recognise.
+    Cli { status: String, stderr: String },
+}
+
+/// What the CLI prints when nothing is listening at `--endpoint-url`.
+const CLI_UNREACHABLE: &str = "Could not connect to the endpoint URL";
+
+impl BackendError {
+    /// The endpoint itself could not be reached, as opposed to a failed call.
+    pub fn is_unreachable(&self) -> bool {
+        match self {
+            BackendError::Unavailable(_) => true,
+            BackendError::Cli { stderr, .. } => stderr.contains(CLI_UNREACHABLE),
+            _ => false,
+        }
+    }
+
+    pub fn stderr(&self) -> Option<&str> {
+        match self {
+            BackendError::Cli { stderr, .. } => Some(stderr),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for BackendError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BackendError::Unavailable(message) => write!(f, "backend unavailable: {message}"),
+            BackendError::Service { kind, message } => write!(f, "{kind}: {message}"),
+            BackendError::Parse(message) => write!(f, "unexpected response: {message}"),
+            BackendError::Timeout(timeout) => write!(f, "timed out after {timeout:?}"),
+            BackendError::Cli { status, .. } => write!(f, "aws CLI exited with {status}"),
+        }
+    }
+}
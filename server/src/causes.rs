@@ -0,0 +1,155 @@
+//! The causal structure of a history: an event's `previousEventId` is its
+//! parent.
+//!
+//! The tree is sent flat, events in id order with the ids of their children,
+//! since a long sequential execution makes it as deep as it is long.
+
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+use crate::history::{Event, EventIndex};
+
+#[derive(Debug, Serialize)]
+pub struct CausalEvent<'a> {
+    #[serde(flatten)]
+    pub event: &'a Event,
+    /// Ids of the events this one caused.
+    pub children: Vec<u64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CausalTree<'a> {
+    /// Events caused by nothing in the history, usually just `ExecutionStarted`.
+    pub roots: Vec<u64>,
+    pub events: Vec<CausalEvent<'a>>,
+}
+
+/// The state visit an event happened in, with the input it was entered with.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct StateInput {
+    pub name: String,
+    #[serde(rename = "enteredEventId")]
+    pub entered_event_id: u64,
+    pub input: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CausalChain<'a> {
+    /// The event asked about first, then its causes back to a root.
+    pub events: Vec<&'a Event>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub state: Option<StateInput>,
+}
+
+pub fn tree(events: &[Event]) -> CausalTree<'_> {
+    let index = EventIndex::new(events);
+    let mut ordered: Vec<&Event> = events.iter().collect();
+    ordered.sort_by_key(|event| event.id);
+
+    let mut roots = Vec::new();
+    let mut children: HashMap<u64, Vec<u64>> = HashMap::new();
+    for event in &ordered {
+        match index.previous(event) {
+            Some(parent) => children.entry(parent.id).or_default().push(event.id),
+            None => roots.push(event.id),
+        }
+    }
+    CausalTree {
+        roots,
+        events: ordered
+            .into_iter()
+            .map(|event| CausalEvent {
+                event,
+                children: children.remove(&event.id).unwrap_or_default(),
+            })
+            .collect(),
+    }
+}
+
+/// What led to event `id`, or `None` if the history has no such event.
+pub fn chain(events: &[Event], id: u64) -> Option<CausalChain<'_>> {
+    let index = EventIndex::new(events);
+    let event = events.iter().find(|event| event.id == id)?;
+    let state = index.entered_state(event).and_then(|entered| {
+        let details = entered.state_entered()?;
+        Some(StateInput {
+            name: details.name.clone(),
+            entered_event_id: entered.id,
+            input: details.input.clone(),
+        })
+    });
+    let mut chain = vec![event];
+    while let Some(previous) = index.previous(chain[chain.len() - 1]) {
+        chain.push(previous);
+    }
+    Some(CausalChain {
+        events: chain,
+        state,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn history() -> Vec<Event> {
+        serde_json::from_value(json!([
+            {"timestamp": "2024-03-01T10:00:01Z", "type": "ExecutionStarted", "id": 1,
+             "executionStartedEventDetails": {"input": "{}", "roleArn": "role"}},
+            {"timestamp": "2024-03-01T10:00:02Z", "type": "TaskStateEntered", "id": 2,
+             "previousEventId": 1, "stateEnteredEventDetails": {"name": "Charge", "input": "{\"total\":3}"}},
+            {"timestamp": "2024-03-01T10:00:03Z", "type": "TaskScheduled", "id": 3, "previousEventId": 2,
+             "taskScheduledEventDetails": {"resourceType": "lambda", "resource": "invoke",
+                                           "region": "us-east-1", "parameters": "{}"}},
+            {"timestamp": "2024-03-01T10:00:04Z", "type": "TaskFailed", "id": 4, "previousEventId": 3,
+             "taskFailedEventDetails": {"resourceType": "lambda", "resource": "invoke", "error": "Declined"}},
+            {"timestamp": "2024-03-01T10:00:05Z", "type": "ExecutionFailed", "id": 5, "previousEventId": 4,
+             "executionFailedEventDetails": {"error": "Declined"}},
+            {"timestamp": "2024-03-01T10:00:06Z", "type": "TaskStateEntered", "id": 6,
+             "previousEventId": 9, "stateEnteredEventDetails": {"name": "Orphan", "input": "{}"}}
+        ]))
+        .unwrap()
+    }
+
+    #[test]
+    fn children_follow_previous_event_ids() {
+        let history = history();
+        let tree = tree(&history);
+        assert_eq!(tree.roots, [1, 6]);
+        let children: Vec<(u64, &[u64])> = tree
+            .events
+            .iter()
+            .map(|node| (node.event.id, node.children.as_slice()))
+            .collect();
+        assert_eq!(
+            children,
+            [
+                (1, &[2][..]),
+                (2, &[3]),
+                (3, &[4]),
+                (4, &[5]),
+                (5, &[]),
+                (6, &[])
+            ]
+        );
+    }
+
+    #[test]
+    fn failures_lead_back_to_the_state_input() {
+        let history = history();
+        let chain = chain(&history, 5).unwrap();
+        let ids: Vec<u64> = chain.events.iter().map(|event| event.id).collect();
+        assert_eq!(ids, [5, 4, 3, 2, 1]);
+        assert_eq!(
+            chain.state,
+            Some(StateInput {
+                name: String::from("Charge"),
+                entered_event_id: 2,
+                input: String::from("{\"total\":3}"),
+            })
+        );
+        assert!(super::chain(&history, 42).is_none());
+    }
+}
@@ -0,0 +1,347 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use clap::{Parser, ValueEnum};
+use serde::Deserialize;
+
+const DEFAULT_CONFIG_FILE: &str = "step-function-ui.toml";
+const DEFAULT_ENDPOINT: &str = "http://localhost:8083";
+const DEFAULT_PORT: u16 = 6969;
+const DEFAULT_BIND: &str = "127.0.0.1";
+const DEFAULT_CORS_ORIGIN: &str = "http://localhost:8080";
+const DEFAULT_TIMEOUT_SECS: u64 = 30;
+const DEFAULT_MAX_CONCURRENCY: usize = 16;
+
+/// Environment variables and the flags they stand in for.
+const ENV_VARS: [(&str, &str); 8] = [
+    ("SFN_UI_CONFIG", "config"),
+    ("STEP_FUNCTIONS_ENDPOINT", "endpoint"),
+    ("STEP_FUNCTIONS_BACKEND", "backend"),
+    ("SFN_UI_PORT", "port"),
+    ("SFN_UI_BIND", "bind"),
+    ("SFN_UI_CORS_ORIGINS", "cors-origin"),
+    ("SFN_UI_TIMEOUT", "timeout"),
+    ("SFN_UI_MAX_CONCURRENCY", "max-concurrency"),
+];
+
+/// Command line flags. Each flag falls back to its environment variable (see
+/// [`ENV_VARS`]), then to the config file, then to the built-in default.
+#[derive(Parser, Debug, Default)]
+#[command(about = "HTTP API behind the Step Functions Local UI")]
+pub struct Args {
+    /// TOML config file [default: ./step-function-ui.toml when present] [env: SFN_UI_CONFIG]
+    #[arg(long)]
+    pub config: Option<PathBuf>,
+
+    /// Step Functions endpoint the backend talks to [env: STEP_FUNCTIONS_ENDPOINT]
+    #[arg(long)]
+    pub endpoint: Option<String>,
+
+    /// How to reach Step Functions [env: STEP_FUNCTIONS_BACKEND]
+    #[arg(long, value_enum)]
+    pub backend: Option<BackendKind>,
+
+    /// Port the API listens on [env: SFN_UI_PORT]
+    #[arg(long)]
+    pub port: Option<u16>,
+
+    /// Address the API binds to [env: SFN_UI_BIND]
+    #[arg(long)]
+    pub bind: Option<String>,
+
+    /// Origin allowed by CORS; repeat the flag or comma-separate for several [env: SFN_UI_CORS_ORIGINS]
+    #[arg(long = "cors-origin", value_delimiter = ',')]
+    pub cors_origins: Option<Vec<String>>,
+
+    /// Seconds a Step Functions call may take before the request fails with 504 [env: SFN_UI_TIMEOUT]
+    #[arg(long = "timeout")]
+    pub timeout_secs: Option<u64>,
+
+    /// Step Functions calls allowed in flight at once; others wait for a slot [env: SFN_UI_MAX_CONCURRENCY]
+    #[arg(long)]
+    pub max_concurrency: Option<usize>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum BackendKind {
+    /// Native AWS JSON protocol client
+    Http,
+    /// Shell out to the `aws` CLI
+    Cli,
+}
+
+/// Shape of the TOML config file; every key is optional.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct FileConfig {
+    pub endpoint: Option<String>,
+    pub backend: Option<BackendKind>,
+    pub port: Option<u16>,
+    pub bind: Option<String>,
+    pub cors_origins: Option<Vec<String>>,
+    #[serde(rename = "timeout")]
+    pub timeout_secs: Option<u64>,
+    pub max_concurrency: Option<usize>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Config {
+    pub endpoint: String,
+    pub backend: BackendKind,
+    pub port: u16,
+    pub bind: String,
+    pub cors_origins: Vec<String>,
+    pub timeout: Duration,
+    pub max_concurrency: usize,
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Read(PathBuf, std::io::Error),
+    Parse(PathBuf, toml::de::Error),
+    /// A setting has a value the server cannot run with.
+    Invalid(&'static str, String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Read(path, e) => write!(f, "cannot read {}: {e}", path.display()),
+            ConfigError::Parse(path, e) => write!(f, "invalid config {}: {e}", path.display()),
+            ConfigError::Invalid(setting, reason) => write!(f, "invalid {setting}: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl Args {
+    /// Reads the flags' environment variables through `env`.
+    pub fn from_env(env: impl Fn(&str) -> Option<String>) -> Result<Args, ConfigError> {
+        let mut args = Args::default();
+        for (var, flag) in ENV_VARS {
+            if let Some(value) = env(var) {
+                args.try_update_from(["server", &format!("--{flag}={value}")])
+                    .map_err(|e| ConfigError::Invalid(var, format!("{}: {value:?}", e.kind())))?;
+            }
+        }
+        Ok(args)
+    }
+
+    /// Fills every flag that was not given from `fallback`.
+    pub fn or(self, fallback: Args) -> Args {
+        Args {
+            config: self.config.or(fallback.config),
+            endpoint: self.endpoint.or(fallback.endpoint),
+            backend: self.backend.or(fallback.backend),
+            port: self.port.or(fallback.port),
+            bind: self.bind.or(fallback.bind),
+            cors_origins: self.cors_origins.or(fallback.cors_origins),
+            timeout_secs: self.timeout_secs.or(fallback.timeout_secs),
+            max_concurrency: self.max_concurrency.or(fallback.max_concurrency),
+        }
+    }
+}
+
+impl FileConfig {
+    fn read(path: &Path) -> Result<FileConfig, ConfigError> {
+        let contents =
+            std::fs::read_to_string(path).map_err(|e| ConfigError::Read(path.to_path_buf(), e))?;
+        toml::from_str(&contents).map_err(|e| ConfigError::Parse(path.to_path_buf(), e))
+    }
+}
+
+impl Config {
+    /// Parses the process arguments and environment, then layers them over the config file.
+    pub fn load() -> Result<Config, ConfigError> {
+        Config::resolve(Args::parse(), |var| std::env::var(var).ok())
+    }
+
+    /// Layers `args` over the environment seen through `env`, then over the config file.
+    pub fn resolve(
+        args: Args,
+        env: impl Fn(&str) -> Option<String>,
+    ) -> Result<Config, ConfigError> {
+        let args = args.or(Args::from_env(env)?);
+        let file = match &args.config {
+            Some(path) => FileConfig::read(path)?,
+            None if Path::new(DEFAULT_CONFIG_FILE).exists() => {
+                FileConfig::read(Path::new(DEFAULT_CONFIG_FILE))?
+            }
+            None => FileConfig::default(),
+        };
+        Config::merge(args, file).validate()
+    }
+
+    /// Rejects settings that would make every backend call fail or never run.
+    pub fn validate(self) -> Result<Config, ConfigError> {
+        if self.timeout.is_zero() {
+            return Err(ConfigError::Invalid(
+                "timeout",
+                String::from("must be at least 1 second"),
+            ));
+        }
+        if self.max_concurrency == 0 {
+            return Err(ConfigError::Invalid(
+                "max-concurrency",
+                String::from("must be at least 1"),
+            ));
+        }
+        Ok(self)
+    }
+
+    pub fn merge(args: Args, file: FileConfig) -> Config {
+        Config {
+            endpoint: args
+                .endpoint
+                .or(file.endpoint)
+                .unwrap_or_else(|| String::from(DEFAULT_ENDPOINT)),
+            backend: args.backend.or(file.backend).unwrap_or(BackendKind::Http),
+            port: args.port.or(file.port).unwrap_or(DEFAULT_PORT),
+            bind: args
+                .bind
+                .or(file.bind)
+                .unwrap_or_else(|| String::from(DEFAULT_BIND)),
+            cors_origins: args
+                .cors_origins
+                .or(file.cors_origins)
+                .unwrap_or_else(|| vec![String::from(DEFAULT_CORS_ORIGIN)]),
+            timeout: Duration::from_secs(
+                args.timeout_secs
+                    .or(file.timeout_secs)
+                    .unwrap_or(DEFAULT_TIMEOUT_SECS),
+            ),
+            max_concurrency: args
+                .max_concurrency
+                .or(file.max_concurrency)
+                .unwrap_or(DEFAULT_MAX_CONCURRENCY),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn defaults_match_the_previous_hardcoded_values() {
+        let config = Config::merge(Args::default(), FileConfig::default());
+        assert_eq!(
+            config,
+            Config {
+                endpoint: String::from("http://localhost:8083"),
+                backend: BackendKind::Http,
+                port: 6969,
+                bind: String::from("127.0.0.1"),
+                cors_origins: vec![String::from("http://localhost:8080")],
+                timeout: Duration::from_secs(30),
+                max_concurrency: 16,
+            }
+        );
+    }
+
+    #[test]
+    fn flags_win_over_the_config_file() {
+        let file: FileConfig = toml::from_str(
+            r#"
+            endpoint = "http://stepfunctions:8083"
+            port = 7000
+            backend = "cli"
+            cors-origins = ["http://ui.local:8080", "http://localhost:8080"]
+            timeout = 5
+            max-concurrency = 2
+            "#,
+        )
+        .unwrap();
+        let args = Args::try_parse_from(["server", "--port", "7100", "--bind", "0.0.0.0"]).unwrap();
+
+        let config = Config::merge(args, file);
+        assert_eq!(config.port, 7100);
+        assert_eq!(config.bind, "0.0.0.0");
+        assert_eq!(config.endpoint, "http://stepfunctions:8083");
+        assert_eq!(config.backend, BackendKind::Cli);
+        assert_eq!(config.cors_origins.len(), 2);
+        assert_eq!(config.timeout, Duration::from_secs(5));
+        assert_eq!(config.max_concurrency, 2);
+    }
+
+    #[test]
+    fn cors_origins_accept_comma_separated_values() {
+        let args = Args::try_parse_from([
+            "server",
+            "--cors-origin",
+            "http://a.local,http://b.local",
+            "--cors-origin",
+            "http://c.local",
+        ])
+        .unwrap();
+        assert_eq!(
+            args.cors_origins.unwrap(),
+            vec!["http://a.local", "http://b.local", "http://c.local"]
+        );
+    }
+
+    #[test]
+    fn zero_timeouts_and_concurrency_are_rejected() {
+        for flag in ["--timeout", "--max-concurrency"] {
+            let args = Args::try_parse_from(["server", flag, "0"]).unwrap();
+            let error = Config::merge(args, FileConfig::default())
+                .validate()
+                .unwrap_err();
+            assert!(matches!(error, ConfigError::Invalid(..)), "{flag}");
+        }
+        let file: FileConfig = toml::from_str("timeout = 0").unwrap();
+        assert!(Config::merge(Args::default(), file).validate().is_err());
+        assert!(Config::merge(Args::default(), FileConfig::default())
+            .validate()
+            .is_ok());
+    }
+
+    #[test]
+    fn flags_win_over_the_environment_which_wins_over_the_file() {
+        let env = HashMap::from([
+            ("SFN_UI_PORT", "7200"),
+            ("SFN_UI_BIND", "0.0.0.0"),
+            ("STEP_FUNCTIONS_BACKEND", "cli"),
+            ("SFN_UI_CORS_ORIGINS", "http://a.local,http://b.local"),
+        ]);
+        let env = Args::from_env(|var| env.get(var).map(|value| value.to_string())).unwrap();
+        let file: FileConfig = toml::from_str(
+            r#"
+            endpoint = "http://stepfunctions:8083"
+            bind = "127.0.0.2"
+            backend = "http"
+            "#,
+        )
+        .unwrap();
+        let args = Args::try_parse_from(["server", "--port", "7100"]).unwrap();
+
+        let config = Config::merge(args.or(env), file);
+        assert_eq!(config.port, 7100);
+        assert_eq!(config.bind, "0.0.0.0");
+        assert_eq!(config.backend, BackendKind::Cli);
+        assert_eq!(config.endpoint, "http://stepfunctions:8083");
+        assert_eq!(
+            config.cors_origins,
+            vec!["http://a.local", "http://b.local"]
+        );
+    }
+
+    #[test]
+    fn bad_environment_values_name_their_variable() {
+        let error = Config::resolve(Args::default(), |var| {
+            (var == "SFN_UI_PORT").then(|| String::from("http"))
+        })
+        .unwrap_err();
+        assert!(matches!(error, ConfigError::Invalid("SFN_UI_PORT", _)));
+        assert!(Config::resolve(Args::default(), |_| None).is_ok());
+    }
+
+    #[test]
+    fn unknown_file_keys_are_rejected() {
+        assert!(toml::from_str::<FileConfig>("prot = 7000").is_err());
+    }
+}
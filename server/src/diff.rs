@@ -0,0 +1,160 @@
+use serde::Serialize;
+use serde_json::Value;
+
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Op {
+    Added,
+    Removed,
+    Changed,
+}
+
+/// One difference between two JSON documents, located by JSON pointer.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Change {
+    pub path: String,
+    pub op: Op,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub old: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub new: Option<Value>,
+}
+
+/// Structural diff of `old` against `new`. Objects are compared key by key and
+/// arrays index by index; any other mismatch is reported at the deepest path.
+pub fn diff(old: &Value, new: &Value) -> Vec<Change> {
+    let mut changes = Vec::new();
+    walk(String::new(), old, new, &mut changes);
+    changes
+}
+
+/// Escapes one JSON pointer reference token (RFC 6901).
+pub fn escape(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+fn walk(path: String, old: &Value, new: &Value, changes: &mut Vec<Change>) {
+    match (old, new) {
+        (Value::Object(old), Value::Object(new)) => {
+            for (key, old_value) in old {
+                let path = format!("{}/{}", path, escape(key));
+                match new.get(key) {
+                    Some(new_value) => walk(path, old_value, new_value, changes),
+                    None => changes.push(Change {
+                        path,
+                        op: Op::Removed,
+                        old: Some(old_value.clone()),
+                        new: None,
+                    }),
+                }
+            }
+            for (key, new_value) in new {
+                if !old.contains_key(key) {
+                    changes.push(Change {
+                        path: format!("{}/{}", path, escape(key)),
+                        op: Op::Added,
+                        old: None,
+                        new: Some(new_value.clone()),
+                    });
+                }
+            }
+        }
+        (Value::Array(old), Value::Array(new)) => {
+            for index in 0..old.len().max(new.len()) {
+                let path = format!("{}/{}", path, index);
+                match (old.get(index), new.get(index)) {
+                    (Some(old_value), Some(new_value)) => walk(path, old_value, new_value, changes),
+                    (Some(old_value), None) => changes.push(Change {
+                        path,
+                        op: Op::Removed,
+                        old: Some(old_value.clone()),
+                        new: None,
+                    }),
+                    (None, Some(new_value)) => changes.push(Change {
+                        path,
+                        op: Op::Added,
+                        old: None,
+                        new: Some(new_value.clone()),
+                    }),
+                    (None, None) => {}
+                }
+            }
+        }
+        _ if old != new => changes.push(Change {
+            path,
+            op: Op::Changed,
+            old: Some(old.clone()),
+            new: Some(new.clone()),
+        }),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn reports_changes_by_json_pointer() {
+        let old = json!({
+            "StartAt": "Validate",
+            "States": {
+                "Validate": {"Type": "Task", "Resource": "arn:validate", "Next": "Done"},
+                "Done": {"Type": "Succeed"},
+                "Old/State": {"Type": "Pass", "End": true}
+            }
+        });
+        let new = json!({
+            "StartAt": "Validate",
+            "States": {
+                "Validate": {"Type": "Task", "Resource": "arn:validate-v2", "Next": "Done",
+                             "Retry": [{"ErrorEquals": ["States.ALL"]}]},
+                "Done": {"Type": "Succeed"}
+            }
+        });
+
+        assert_eq!(
+            diff(&old, &new),
+            vec![
+                Change {
+                    path: String::from("/States/Old~1State"),
+                    op: Op::Removed,
+                    old: Some(json!({"Type": "Pass", "End": true})),
+                    new: None,
+                },
+                Change {
+                    path: String::from("/States/Validate/Resource"),
+                    op: Op::Changed,
+                    old: Some(json!("arn:validate")),
+                    new: Some(json!("arn:validate-v2")),
+                },
+                Change {
+                    path: String::from("/States/Validate/Retry"),
+                    op: Op::Added,
+                    old: None,
+                    new: Some(json!([{"ErrorEquals": ["States.ALL"]}])),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn identical_documents_have_no_changes() {
+        let document = json!({"a": [1, {"b": null}]});
+        assert!(diff(&document, &document).is_empty());
+    }
+
+    #[test]
+    fn arrays_are_compared_by_index() {
+        assert_eq!(
+            diff(&json!([1, 2]), &json!([1])),
+            vec![Change {
+                path: String::from("/1"),
+                op: Op::Removed,
+                old: Some(json!(2)),
+                new: None,
+            }]
+        );
+    }
+}
@@ -0,0 +1,221 @@
+//! Everything a handler can fail with, and the response each failure becomes.
+//!
+//! Every error body is a `ServerError`: a machine-readable `code`, a message
+//! for people and, when the CLI backend failed, what it printed to stderr.
+
+use std::fmt;
+
+use actix_web::http::header::ContentType;
+use actix_web::http::StatusCode;
+use actix_web::{HttpResponse, ResponseError};
+
+use crate::backend::BackendError;
+use crate::model::ServerError;
+use crate::validate::ValidationError;
+
+#[derive(Debug)]
+pub enum ApiError {
+    /// The request itself was rejected before reaching the backend.
+    Validation(ValidationError),
+    /// `operation` is the Step Functions API call that failed.
+    Backend {
+        operation: &'static str,
+        error: BackendError,
+    },
+    /// Deleting the state machine would stop these executions; needs `force=true`.
+    RunningExecutions { arn: String, count: usize },
+    /// The execution's history has no event with this id.
+    UnknownEvent { arn: String, id: u64 },
+    /// The backend answered but the result could not be used.
+    Internal(String),
+}
+
+impl ApiError {
+    pub fn backend(operation: &'static str, error: BackendError) -> Self {
+        ApiError::Backend { operation, error }
+    }
+
+    /// The JSON body of the error response.
+    pub fn body(&self) -> ServerError {
+        let stderr = match self {
+            ApiError::Backend { error, .. } => error.stderr().map(str::to_string),
+            _ => None,
+        };
+        ServerError {
+            code: Some(self.code()),
+            message: format!("ERROR: {}", self),
+            stderr,
+        }
+    }
+
+    fn code(&self) -> String {
+        match self {
+            ApiError::Validation(error) => error.code().to_string(),
+            ApiError::Backend { error, .. } => match error {
+                BackendError::Service { kind, .. } => kind.clone(),
+                error if error.is_unreachable() => String::from("BackendUnavailable"),
+                BackendError::Timeout(_) => String::from("BackendTimeout"),
+                _ => String::from("BackendFailed"),
+            },
+            ApiError::RunningExecutions { .. } => String::from("RunningExecutions"),
+            ApiError::UnknownEvent { .. } => String::from("EventDoesNotExist"),
+            ApiError::Internal(_) => String::from("InternalError"),
+        }
+    }
+}
+
+/// Status for an AWS error code, by the naming conventions Step Functions uses.
+fn service_status(kind: &str) -> StatusCode {
+    if kind.ends_with("DoesNotExist") || kind == "ResourceNotFound" {
+        StatusCode::NOT_FOUND
+    } else if kind.ends_with("AlreadyExists") || kind.ends_with("Conflict") {
+        StatusCode::CONFLICT
+    } else if kind.starts_with("Invalid") || kind == "ValidationException" {
+        StatusCode::BAD_REQUEST
+    } else if kind.contains("Throttling") || kind.ends_with("LimitExceeded") {
+        StatusCode::TOO_MANY_REQUESTS
+    } else {
+        StatusCode::BAD_GATEWAY
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApiError::Validation(error) => write!(f, "{error}"),
+            ApiError::Backend { operation, error } => {
+                write!(f, "\"{operation}\" failed. {error}")
+            }
+            ApiError::RunningExecutions { arn, count } => write!(
+                f,
+                "{arn} has {count} running execution(s); pass force=true to delete it anyway."
+            ),
+            ApiError::UnknownEvent { arn, id } => {
+                write!(f, "{arn} has no event with id {id}.")
+            }
+            ApiError::Internal(message) => f.write_str(message),
+        }
+    }
+}
+
+impl From<ValidationError> for ApiError {
+    fn from(error: ValidationError) -> Self {
+        ApiError::Validation(error)
+    }
+}
+
+impl ResponseError for ApiError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            ApiError::Validation(error) => error.status_code(),
+            ApiError::Backend { error, .. } => match error {
+                BackendError::Service { kind, .. } => service_status(kind),
+                error if error.is_unreachable() => StatusCode::SERVICE_UNAVAILABLE,
+                BackendError::Timeout(_) => StatusCode::GATEWAY_TIMEOUT,
+                _ => StatusCode::BAD_GATEWAY,
+            },
+            ApiError::RunningExecutions { .. } => StatusCode::CONFLICT,
+            ApiError::UnknownEvent { .. } => StatusCode::NOT_FOUND,
+            ApiError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        HttpResponse::build(self.status_code())
+            .content_type(ContentType::json())
+            .json(self.body())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn service(kind: &str) -> ApiError {
+        ApiError::backend(
+            "DescribeExecution",
+            BackendError::Service {
+                kind: kind.to_string(),
+                message: String::from("details"),
+            },
+        )
+    }
+
+    #[test]
+    fn service_errors_map_by_kind() {
+        for (kind, status) in [
+            ("ExecutionDoesNotExist", StatusCode::NOT_FOUND),
+            ("StateMachineDoesNotExist", StatusCode::NOT_FOUND),
+            ("ResourceNotFound", StatusCode::NOT_FOUND),
+            ("InvalidArn", StatusCode::BAD_REQUEST),
+            ("InvalidToken", StatusCode::BAD_REQUEST),
+            ("ExecutionAlreadyExists", StatusCode::CONFLICT),
+            ("ThrottlingException", StatusCode::TOO_MANY_REQUESTS),
+            ("ExecutionLimitExceeded", StatusCode::TOO_MANY_REQUESTS),
+            ("InternalFailure", StatusCode::BAD_GATEWAY),
+        ] {
+            let error = service(kind);
+            assert_eq!(error.status_code(), status, "{kind}");
+            assert_eq!(error.code(), kind);
+        }
+    }
+
+    #[test]
+    fn transport_failures_map_to_gateway_statuses() {
+        for (error, status, code) in [
+            (
+                BackendError::Unavailable(String::from("connection refused")),
+                StatusCode::SERVICE_UNAVAILABLE,
+                "BackendUnavailable",
+            ),
+            (
+                BackendError::Cli {
+                    status: String::from("exit status: 255"),
+                    stderr: String::from(
+                        "Could not connect to the endpoint URL: \"http://localhost:8083/\"",
+                    ),
+                },
+                StatusCode::SERVICE_UNAVAILABLE,
+                "BackendUnavailable",
+            ),
+            (
+                BackendError::Timeout(Duration::from_secs(30)),
+                StatusCode::GATEWAY_TIMEOUT,
+                "BackendTimeout",
+            ),
+            (
+                BackendError::Parse(String::from("expected value")),
+                StatusCode::BAD_GATEWAY,
+                "BackendFailed",
+            ),
+        ] {
+            let error = ApiError::backend("ListStateMachines", error);
+            assert_eq!(error.status_code(), status, "{error}");
+            assert_eq!(error.code(), code);
+        }
+    }
+
+    #[actix_web::test]
+    async fn cli_failures_include_stderr() {
+        let error = ApiError::backend(
+            "ListStateMachines",
+            BackendError::Cli {
+                status: String::from("signal: 9 (SIGKILL)"),
+                stderr: String::from("Killed"),
+            },
+        );
+        let response = error.error_response();
+        assert_eq!(response.status(), StatusCode::BAD_GATEWAY);
+        let body = actix_web::body::to_bytes(response.into_body())
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["code"], "BackendFailed");
+        assert_eq!(body["stderr"], "Killed");
+        assert_eq!(
+            body["message"],
+            "ERROR: \"ListStateMachines\" failed. aws CLI exited with signal: 9 (SIGKILL)"
+        );
+    }
+}
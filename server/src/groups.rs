@@ -0,0 +1,468 @@
+//! A history split by Map iteration and Parallel branch.
+//!
+//! Every Map or Parallel state visit becomes a group with one lane per
+//! iteration or branch. Lanes hold their own events and the groups nested in
+//! them, so a failure deep inside a Map points at the iteration it ended.
+
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+use crate::asl::graph::Graph;
+use crate::history::{Event, EventDetails, EventIndex};
+use crate::steps::{branch_indexes, scope};
+use crate::timestamp::Timestamp;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum Status {
+    Running,
+    Succeeded,
+    Failed,
+    Aborted,
+}
+
+/// A Map iteration, a Parallel branch, or the execution itself at the root.
+#[derive(Debug, Serialize)]
+pub struct Lane<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub iteration: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub branch: Option<usize>,
+    pub status: Status,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub started: Option<Timestamp>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ended: Option<Timestamp>,
+    #[serde(rename = "durationMillis", skip_serializing_if = "Option::is_none")]
+    pub duration_millis: Option<i64>,
+    pub events: Vec<&'a Event>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub groups: Vec<Group<'a>>,
+}
+
+/// One visit of a Map or Parallel state. Its own events (entered, started,
+/// exited, ...) are in the enclosing lane.
+#[derive(Debug, Serialize)]
+pub struct Group<'a> {
+    pub state: String,
+    #[serde(rename = "enteredEventId")]
+    pub entered_event_id: u64,
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    pub status: Status,
+    pub entered: Timestamp,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exited: Option<Timestamp>,
+    #[serde(rename = "durationMillis", skip_serializing_if = "Option::is_none")]
+    pub duration_millis: Option<i64>,
+    pub lanes: Vec<Lane<'a>>,
+}
+
+/// Owner's `StateEntered` id, iteration and branch; all `None` at the root.
+type LaneKey = (Option<u64>, Option<u64>, Option<usize>);
+
+const ROOT: LaneKey = (None, None, None);
+
+#[derive(Default)]
+struct LaneState<'a> {
+    events: Vec<&'a Event>,
+    visits: Vec<u64>,
+    end: Option<Status>,
+    groups: Vec<u64>,
+}
+
+#[derive(Default)]
+struct VisitState {
+    exited: bool,
+    aborted: bool,
+    failed_last: bool,
+    fail_state: bool,
+}
+
+struct GroupState<'a> {
+    entered: &'a Event,
+    kind: &'static str,
+    lanes: Vec<LaneKey>,
+    outcome: Option<Status>,
+    exited: Option<Timestamp>,
+}
+
+struct Builder<'a> {
+    lanes: HashMap<LaneKey, LaneState<'a>>,
+    visits: HashMap<u64, VisitState>,
+    groups: HashMap<u64, GroupState<'a>>,
+    finished: bool,
+}
+
+impl<'a> Builder<'a> {
+    fn lane(&mut self, key: LaneKey) -> &mut LaneState<'a> {
+        if !self.lanes.contains_key(&key) {
+            if let Some(group) = key.0.and_then(|owner| self.groups.get_mut(&owner)) {
+                group.lanes.push(key);
+            }
+        }
+        self.lanes.entry(key).or_default()
+    }
+
+    fn build_lane(&mut self, key: LaneKey, closed: bool) -> Lane<'a> {
+        let state = self.lanes.remove(&key).unwrap_or_default();
+        let visits: Vec<&VisitState> = state
+            .visits
+            .iter()
+            .filter_map(|visit| self.visits.get(visit))
+            .collect();
+        let open: Vec<&&VisitState> = visits
+            .iter()
+            .filter(|visit| !visit.exited && !visit.aborted)
+            .collect();
+        let status = if let Some(end) = state.end {
+            end
+        } else if open.iter().any(|visit| visit.fail_state) {
+            Status::Failed
+        } else if !open.is_empty() {
+            match closed {
+                true if open.iter().any(|visit| visit.failed_last) => Status::Failed,
+                true => Status::Aborted,
+                false => Status::Running,
+            }
+        } else if visits.iter().any(|visit| visit.aborted) {
+            Status::Aborted
+        } else if !visits.is_empty() {
+            Status::Succeeded
+        } else if closed {
+            Status::Aborted
+        } else {
+            Status::Running
+        };
+
+        let started = state.events.first().map(|event| event.timestamp);
+        let ended = match status {
+            Status::Running => None,
+            _ => state.events.last().map(|event| event.timestamp),
+        };
+        Lane {
+            iteration: key.1,
+            branch: key.2,
+            status,
+            started,
+            ended,
+            duration_millis: started.zip(ended).map(|(from, to)| to.millis_since(&from)),
+            events: state.events,
+            groups: state
+                .groups
+                .into_iter()
+                .filter_map(|group| self.build_group(group))
+                .collect(),
+        }
+    }
+
+    fn build_group(&mut self, id: u64) -> Option<Group<'a>> {
+        let mut state = self.groups.remove(&id)?;
+        let known = state.outcome.or(state.exited.map(|_| Status::Succeeded));
+        let closed = known.is_some() || self.finished;
+        state.lanes.sort();
+        let lanes: Vec<Lane> = state
+            .lanes
+            .iter()
+            .map(|&key| self.build_lane(key, closed))
+            .collect();
+        let status = known.unwrap_or(match self.finished {
+            true if lanes.iter().any(|lane| lane.status == Status::Failed) => Status::Failed,
+            true => Status::Aborted,
+            false => Status::Running,
+        });
+        let entered = state.entered.timestamp;
+        Some(Group {
+            state: state.entered.state_entered()?.name.clone(),
+            entered_event_id: id,
+            kind: state.kind,
+            status,
+            entered,
+            exited: state.exited,
+            duration_millis: state.exited.map(|exited| exited.millis_since(&entered)),
+            lanes,
+        })
+    }
+}
+
+/// The outcome a Map or Parallel event reports for its state visit.
+fn group_outcome(event: &Event) -> Option<Status> {
+    match event.details {
+        EventDetails::MapStateSucceeded | EventDetails::ParallelStateSucceeded => {
+            Some(Status::Succeeded)
+        }
+        EventDetails::MapStateFailed | EventDetails::ParallelStateFailed => Some(Status::Failed),
+        EventDetails::MapStateAborted | EventDetails::ParallelStateAborted => Some(Status::Aborted),
+        _ => None,
+    }
+}
+
+/// The iteration a `MapIteration*` event is about, and how it ended if it did.
+fn iteration(event: &Event) -> Option<(Option<u64>, Option<Status>)> {
+    match &event.details {
+        EventDetails::MapIterationStarted { details } => Some((details.index, None)),
+        EventDetails::MapIterationSucceeded { details } => {
+            Some((details.index, Some(Status::Succeeded)))
+        }
+        EventDetails::MapIterationFailed { details } => Some((details.index, Some(Status::Failed))),
+        EventDetails::MapIterationAborted { details } => {
+            Some((details.index, Some(Status::Aborted)))
+        }
+        _ => None,
+    }
+}
+
+fn execution_outcome(event: &Event) -> Option<Status> {
+    match event.details {
+        EventDetails::ExecutionSucceeded { .. } => Some(Status::Succeeded),
+        EventDetails::ExecutionFailed { .. } | EventDetails::ExecutionTimedOut { .. } => {
+            Some(Status::Failed)
+        }
+        EventDetails::ExecutionAborted { .. } => Some(Status::Aborted),
+        _ => None,
+    }
+}
+
+/// The history as a tree of lanes, rooted at the execution.
+pub fn group<'a>(graph: &Graph, events: &'a [Event]) -> Lane<'a> {
+    let index = EventIndex::new(events);
+    let branches = branch_indexes(graph);
+    let mut ordered: Vec<&Event> = events.iter().collect();
+    ordered.sort_by_key(|event| event.id);
+
+    let mut builder = Builder {
+        lanes: HashMap::new(),
+        visits: HashMap::new(),
+        groups: HashMap::new(),
+        finished: false,
+    };
+    let mut lane_of_visit: HashMap<u64, LaneKey> = HashMap::new();
+    for event in ordered {
+        if let Some(outcome) = execution_outcome(event) {
+            builder.finished = true;
+            let root = builder.lane(ROOT);
+            root.events.push(event);
+            root.end = Some(outcome);
+            continue;
+        }
+        if event.state_entered().is_some() {
+            let key = scope(&index, &branches, event).map_or(ROOT, |scope| {
+                (Some(scope.entered_event_id), scope.iteration, scope.branch)
+            });
+            lane_of_visit.insert(event.id, key);
+            builder.visits.insert(
+                event.id,
+                VisitState {
+                    fail_state: matches!(event.details, EventDetails::FailStateEntered { .. }),
+                    ..VisitState::default()
+                },
+            );
+            let kind = match event.details {
+                EventDetails::MapStateEntered { .. } => Some("Map"),
+                EventDetails::ParallelStateEntered { .. } => Some("Parallel"),
+                _ => None,
+            };
+            if let Some(kind) = kind {
+                builder.groups.insert(
+                    event.id,
+                    GroupState {
+                        entered: event,
+                        kind,
+                        lanes: Vec::new(),
+                        outcome: None,
+                        exited: None,
+                    },
+                );
+            }
+            let lane = builder.lane(key);
+            lane.events.push(event);
+            lane.visits.push(event.id);
+            if kind.is_some() {
+                lane.groups.push(event.id);
+            }
+            continue;
+        }
+
+        let Some(visit) = index.entered_state(event).map(|entered| entered.id) else {
+            builder.lane(ROOT).events.push(event);
+            continue;
+        };
+        if let Some((iteration, end)) = iteration(event) {
+            let lane = builder.lane((Some(visit), iteration, None));
+            lane.events.push(event);
+            lane.end = lane.end.or(end);
+            continue;
+        }
+        if let Some(group) = builder.groups.get_mut(&visit) {
+            group.outcome = group.outcome.or(group_outcome(event));
+            if event.state_exited().is_some() {
+                group.exited = Some(event.timestamp);
+            }
+        }
+        if let Some(state) = builder.visits.get_mut(&visit) {
+            if event.state_exited().is_some() {
+                state.exited = true;
+            } else if event.is_state_aborted() {
+                state.aborted = true;
+            } else if event.failure().is_some() {
+                state.failed_last = true;
+            } else if event.is_success() {
+                state.failed_last = false;
+            }
+        }
+        let key = lane_of_visit.get(&visit).copied().unwrap_or(ROOT);
+        builder.lane(key).events.push(event);
+    }
+
+    let finished = builder.finished;
+    builder.build_lane(ROOT, finished)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+
+    fn event(id: u64, previous: u64, kind: &str, details: Option<(&str, Value)>) -> Value {
+        let mut event = json!({
+            "timestamp": format!("2024-03-01T10:00:{:02}Z", id),
+            "type": kind,
+            "id": id,
+            "previousEventId": previous
+        });
+        if let Some((key, details)) = details {
+            event[key] = details;
+        }
+        event
+    }
+
+    fn state(id: u64, previous: u64, kind: &str, name: &str) -> Value {
+        let (key, details) = match kind.strip_suffix("Entered") {
+            Some(_) => (
+                "stateEnteredEventDetails",
+                json!({"name": name, "input": "{}"}),
+            ),
+            None => (
+                "stateExitedEventDetails",
+                json!({"name": name, "output": "{}"}),
+            ),
+        };
+        event(id, previous, kind, Some((key, details)))
+    }
+
+    fn map_iteration(id: u64, previous: u64, kind: &str, index: u64) -> Value {
+        let key = format!("m{}EventDetails", &kind[1..]);
+        let details = json!({"name": "Each", "index": index});
+        event(id, previous, kind, Some((&key, details)))
+    }
+
+    #[test]
+    fn map_iterations_become_lanes() {
+        let failed = json!({"resourceType": "lambda", "resource": "invoke", "error": "Boom"});
+        let history: Vec<Event> = serde_json::from_value(json!([
+            state(2, 1, "MapStateEntered", "Each"),
+            event(
+                3,
+                2,
+                "MapStateStarted",
+                Some(("mapStateStartedEventDetails", json!({"length": 2})))
+            ),
+            map_iteration(4, 3, "MapIterationStarted", 0),
+            map_iteration(5, 3, "MapIterationStarted", 1),
+            state(6, 4, "PassStateEntered", "Work"),
+            state(7, 5, "TaskStateEntered", "Charge"),
+            state(8, 6, "PassStateExited", "Work"),
+            map_iteration(9, 8, "MapIterationSucceeded", 0),
+            event(
+                10,
+                7,
+                "TaskFailed",
+                Some(("taskFailedEventDetails", failed))
+            ),
+            map_iteration(11, 10, "MapIterationFailed", 1),
+            event(12, 11, "MapStateFailed", None),
+            event(
+                13,
+                12,
+                "ExecutionFailed",
+                Some(("executionFailedEventDetails", json!({"error": "Boom"})))
+            ),
+        ]))
+        .unwrap();
+        let root = group(&Graph::default(), &history);
+        assert_eq!(root.status, Status::Failed);
+        let ids: Vec<u64> = root.events.iter().map(|event| event.id).collect();
+        assert_eq!(ids, [2, 3, 12, 13]);
+
+        let each = &root.groups[0];
+        assert_eq!((each.state.as_str(), each.kind), ("Each", "Map"));
+        assert_eq!(each.status, Status::Failed);
+        let lanes: Vec<(Option<u64>, Status, Option<i64>)> = each
+            .lanes
+            .iter()
+            .map(|lane| (lane.iteration, lane.status, lane.duration_millis))
+            .collect();
+        assert_eq!(
+            lanes,
+            [
+                (Some(0), Status::Succeeded, Some(5000)),
+                (Some(1), Status::Failed, Some(6000)),
+            ]
+        );
+        let ids: Vec<u64> = each.lanes[1].events.iter().map(|event| event.id).collect();
+        assert_eq!(ids, [5, 7, 10, 11]);
+    }
+
+    #[test]
+    fn parallel_branches_of_an_unfinished_execution() {
+        let document = include_str!("../fixtures/asl/parallel.asl.json");
+        let graph =
+            crate::asl::graph::DefinitionGraph::new(crate::asl::lenient::parse(document).unwrap())
+                .graph;
+        let history: Vec<Event> = serde_json::from_value(json!([
+            state(2, 1, "ParallelStateEntered", "Notify"),
+            event(3, 2, "ParallelStateStarted", None),
+            state(4, 3, "TaskStateEntered", "SendEmail"),
+            state(5, 3, "TaskStateEntered", "SendSms"),
+            state(6, 4, "TaskStateExited", "SendEmail"),
+        ]))
+        .unwrap();
+        let root = group(&graph, &history);
+        assert_eq!(root.status, Status::Running);
+        let notify = &root.groups[0];
+        assert_eq!(notify.status, Status::Running);
+        let lanes: Vec<(Option<usize>, Status)> = notify
+            .lanes
+            .iter()
+            .map(|lane| (lane.branch, lane.status))
+            .collect();
+        assert_eq!(
+            lanes,
+            [(Some(0), Status::Succeeded), (Some(1), Status::Running)]
+        );
+        assert_eq!(notify.lanes[1].ended, None);
+    }
+
+    #[test]
+    fn aborted_branches_end_with_their_parallel() {
+        let document = include_str!("../fixtures/asl/parallel.asl.json");
+        let graph =
+            crate::asl::graph::DefinitionGraph::new(crate::asl::lenient::parse(document).unwrap())
+                .graph;
+        let history: Vec<Event> = serde_json::from_value(json!([
+            state(2, 1, "ParallelStateEntered", "Notify"),
+            event(3, 2, "ParallelStateStarted", None),
+            state(4, 3, "TaskStateEntered", "SendEmail"),
+            event(5, 4, "TaskStateAborted", None),
+            event(6, 5, "ParallelStateAborted", None),
+        ]))
+        .unwrap();
+        let root = group(&graph, &history);
+        let notify = &root.groups[0];
+        assert_eq!(notify.status, Status::Aborted);
+        assert_eq!(notify.lanes[0].status, Status::Aborted);
+        let ids: Vec<u64> = root.events.iter().map(|event| event.id).collect();
+        assert_eq!(ids, [2, 3, 6]);
+    }
+}
@@ -0,0 +1,1082 @@
+//! Execution history events.
+//!
+//! Each event carries at most one `<kind>EventDetails` member, chosen by its
+//! `type`. `EventDetails` models every type Step Functions emits; types this
+//! server does not know yet are kept verbatim in `EventDetails::Other`.
+
+use std::collections::HashMap;
+
+use serde::ser::Error as _;
+use serde::{Deserialize, Serialize, Serializer};
+use serde_json::{Map, Value};
+
+use crate::timestamp::Timestamp;
+
+/// Detail members the frontend decoders expect on every event, null when absent.
+const LEGACY_DETAILS: [&str; 6] = [
+    "executionStartedEventDetails",
+    "executionSucceededEventDetails",
+    "lambdaFunctionFailedEventDetails",
+    "lambdaFunctionScheduledEventDetails",
+    "stateEnteredEventDetails",
+    "stateExitedEventDetails",
+];
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct Event {
+    pub timestamp: Timestamp,
+    pub id: u64,
+    #[serde(rename = "previousEventId", default)]
+    pub previous_event_id: Option<u64>,
+    #[serde(flatten)]
+    pub details: EventDetails,
+}
+
+impl Event {
+    pub fn execution_started(&self) -> Option<&ExecutionStartedEventDetails> {
+        match &self.details {
+            EventDetails::ExecutionStarted { details } => Some(details),
+            _ => None,
+        }
+    }
+
+    /// Details of any `<Type>StateEntered` event.
+    pub fn state_entered(&self) -> Option<&StateEnteredEventDetails> {
+        match &self.details {
+            EventDetails::ChoiceStateEntered { details }
+            | EventDetails::FailStateEntered { details }
+            | EventDetails::MapStateEntered { details }
+            | EventDetails::ParallelStateEntered { details }
+            | EventDetails::PassStateEntered { details }
+            | EventDetails::SucceedStateEntered { details }
+            | EventDetails::TaskStateEntered { details }
+            | EventDetails::WaitStateEntered { details } => Some(details),
+            _ => None,
+        }
+    }
+
+    /// Details of any `<Type>StateExited` event.
+    pub fn state_exited(&self) -> Option<&StateExitedEventDetails> {
+        match &self.details {
+            EventDetails::ChoiceStateExited { details }
+            | EventDetails::MapStateExited { details }
+            | EventDetails::ParallelStateExited { details }
+            | EventDetails::PassStateExited { details }
+            | EventDetails::SucceedStateExited { details }
+            | EventDetails::TaskStateExited { details }
+            | EventDetails::WaitStateExited { details } => Some(details),
+            _ => None,
+        }
+    }
+
+    /// Error and cause of an event reporting that work inside a state failed.
+    /// Failures of the execution itself are not included.
+    pub fn failure(&self) -> Option<Failure<'_>> {
+        fn failure<'a>(error: &'a Option<String>, cause: &'a Option<String>) -> Failure<'a> {
+            Failure {
+                error: error.as_deref(),
+                cause: cause.as_deref(),
+            }
+        }
+        match &self.details {
+            EventDetails::ActivityFailed { details }
+            | EventDetails::ActivityScheduleFailed { details }
+            | EventDetails::ActivityTimedOut { details }
+            | EventDetails::LambdaFunctionScheduleFailed { details }
+            | EventDetails::LambdaFunctionStartFailed { details }
+            | EventDetails::LambdaFunctionTimedOut { details }
+            | EventDetails::MapRunFailed { details } => {
+                Some(failure(&details.error, &details.cause))
+            }
+            EventDetails::TaskStartFailed { details }
+            | EventDetails::TaskSubmitFailed { details }
+            | EventDetails::TaskFailed { details }
+            | EventDetails::TaskTimedOut { details } => {
+                Some(failure(&details.error, &details.cause))
+            }
+            EventDetails::EvaluationFailed { details } => {
+                Some(failure(&details.error, &details.cause))
+            }
+            EventDetails::LambdaFunctionFailed { details } => Some(Failure {
+                error: Some(&details.error),
+                cause: Some(&details.cause),
+            }),
+            EventDetails::MapStateFailed | EventDetails::ParallelStateFailed => Some(Failure {
+                error: None,
+                cause: None,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Whether the event reports that work inside a state succeeded.
+    pub fn is_success(&self) -> bool {
+        matches!(
+            self.details,
+            EventDetails::ActivitySucceeded { .. }
+                | EventDetails::LambdaFunctionSucceeded { .. }
+                | EventDetails::TaskSucceeded { .. }
+                | EventDetails::MapStateSucceeded
+                | EventDetails::ParallelStateSucceeded
+        )
+    }
+
+    /// Whether the event reports that a state visit was aborted instead of exiting.
+    pub fn is_state_aborted(&self) -> bool {
+        matches!(
+            self.details,
+            EventDetails::TaskStateAborted
+                | EventDetails::WaitStateAborted
+                | EventDetails::MapStateAborted
+                | EventDetails::ParallelStateAborted
+        )
+    }
+
+    /// Whether the event ends a Map iteration, however it ended.
+    pub fn is_iteration_end(&self) -> bool {
+        matches!(
+            self.details,
+            EventDetails::MapIterationSucceeded { .. }
+                | EventDetails::MapIterationFailed { .. }
+                | EventDetails::MapIterationAborted { .. }
+        )
+    }
+
+    /// Whether the event ends the execution, however it ended.
+    pub fn is_execution_end(&self) -> bool {
+        matches!(
+            self.details,
+            EventDetails::ExecutionSucceeded { .. }
+                | EventDetails::ExecutionFailed { .. }
+                | EventDetails::ExecutionAborted { .. }
+                | EventDetails::ExecutionTimedOut { .. }
+        )
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Failure<'a> {
+    pub error: Option<&'a str>,
+    pub cause: Option<&'a str>,
+}
+
+/// Events by id, for following `previousEventId` back through a history.
+pub struct EventIndex<'a> {
+    by_id: HashMap<u64, &'a Event>,
+}
+
+impl<'a> EventIndex<'a> {
+    pub fn new(events: &'a [Event]) -> Self {
+        EventIndex {
+            by_id: events.iter().map(|event| (event.id, event)).collect(),
+        }
+    }
+
+    /// The event that caused `event`. Links that do not point to an earlier
+    /// event are ignored, so walking back always ends.
+    pub fn previous(&self, event: &Event) -> Option<&'a Event> {
+        event
+            .previous_event_id
+            .filter(|&id| id < event.id)
+            .and_then(|id| self.by_id.get(&id).copied())
+    }
+
+    /// The `StateEntered` event of the state visit `event` belongs to: the
+    /// event itself if it is one, else the nearest one it descends from.
+    /// Visits that finished on the way back (the branches of a Parallel
+    /// that is exiting, say) are skipped whole.
+    /// Map iterations that ended are skipped the same way, so the end of an
+    /// iteration belongs to its Map state.
+    pub fn entered_state(&self, event: &'a Event) -> Option<&'a Event> {
+        if event.state_entered().is_some() {
+            return Some(event);
+        }
+        let mut current = if event.is_iteration_end() {
+            self.iteration_started(event)
+        } else {
+            self.previous(event)
+        };
+        while let Some(event) = current {
+            if event.state_entered().is_some() {
+                return Some(event);
+            }
+            current = self.skip_finished(event);
+        }
+        None
+    }
+
+    /// The `MapIterationStarted` event of the iteration `end` ended.
+    fn iteration_started(&self, end: &'a Event) -> Option<&'a Event> {
+        let mut current = self.previous(end);
+        while let Some(event) = current {
+            if let EventDetails::MapIterationStarted { .. } = event.details {
+                return Some(event);
+            }
+            current = self.skip_finished(event);
+        }
+        None
+    }
+
+    /// The event before `event`, or before the whole state visit or Map
+    /// iteration that `event` finishes, by exiting or being aborted.
+    fn skip_finished(&self, event: &'a Event) -> Option<&'a Event> {
+        if event.state_exited().is_some() || event.is_state_aborted() {
+            self.entered_state(event)
+                .and_then(|entered| self.previous(entered))
+        } else if event.is_iteration_end() {
+            self.iteration_started(event)
+                .and_then(|started| self.previous(started))
+        } else {
+            self.previous(event)
+        }
+    }
+}
+
+impl Serialize for Event {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let Value::Object(details) =
+            serde_json::to_value(&self.details).map_err(S::Error::custom)?
+        else {
+            return Err(S::Error::custom("event details must be an object"));
+        };
+        let mut event = Map::new();
+        event.insert(
+            String::from("timestamp"),
+            Value::String(self.timestamp.to_string()),
+        );
+        event.insert(String::from("id"), Value::from(self.id));
+        event.insert(
+            String::from("previousEventId"),
+            self.previous_event_id.map_or(Value::Null, Value::from),
+        );
+        event.extend(details);
+        for key in LEGACY_DETAILS {
+            event.entry(key).or_insert(Value::Null);
+        }
+        event.serialize(serializer)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "type")]
+pub enum EventDetails {
+    ActivityFailed {
+        #[serde(rename = "activityFailedEventDetails")]
+        details: FailureEventDetails,
+    },
+    ActivityScheduleFailed {
+        #[serde(rename = "activityScheduleFailedEventDetails")]
+        details: FailureEventDetails,
+    },
+    ActivityScheduled {
+        #[serde(rename = "activityScheduledEventDetails")]
+        details: ActivityScheduledEventDetails,
+    },
+    ActivityStarted {
+        #[serde(rename = "activityStartedEventDetails")]
+        details: ActivityStartedEventDetails,
+    },
+    ActivitySucceeded {
+        #[serde(rename = "activitySucceededEventDetails")]
+        details: OutputEventDetails,
+    },
+    ActivityTimedOut {
+        #[serde(rename = "activityTimedOutEventDetails")]
+        details: FailureEventDetails,
+    },
+
+    ExecutionStarted {
+        #[serde(rename = "executionStartedEventDetails")]
+        details: ExecutionStartedEventDetails,
+    },
+    ExecutionSucceeded {
+        #[serde(rename = "executionSucceededEventDetails")]
+        details: ExecutionSucceededEventDetails,
+    },
+    ExecutionFailed {
+        #[serde(rename = "executionFailedEventDetails")]
+        details: FailureEventDetails,
+    },
+    ExecutionAborted {
+        #[serde(rename = "executionAbortedEventDetails")]
+        details: FailureEventDetails,
+    },
+    ExecutionTimedOut {
+        #[serde(rename = "executionTimedOutEventDetails")]
+        details: FailureEventDetails,
+    },
+    ExecutionRedriven {
+        #[serde(rename = "executionRedrivenEventDetails")]
+        details: RedrivenEventDetails,
+    },
+
+    LambdaFunctionScheduled {
+        #[serde(rename = "lambdaFunctionScheduledEventDetails")]
+        details: LambdaFunctionScheduledEventDetails,
+    },
+    LambdaFunctionScheduleFailed {
+        #[serde(rename = "lambdaFunctionScheduleFailedEventDetails")]
+        details: FailureEventDetails,
+    },
+    LambdaFunctionStarted,
+    LambdaFunctionStartFailed {
+        #[serde(rename = "lambdaFunctionStartFailedEventDetails")]
+        details: FailureEventDetails,
+    },
+    LambdaFunctionSucceeded {
+        #[serde(rename = "lambdaFunctionSucceededEventDetails")]
+        details: OutputEventDetails,
+    },
+    LambdaFunctionFailed {
+        #[serde(rename = "lambdaFunctionFailedEventDetails")]
+        details: LambdaFunctionFailedEventDetails,
+    },
+    LambdaFunctionTimedOut {
+        #[serde(rename = "lambdaFunctionTimedOutEventDetails")]
+        details: FailureEventDetails,
+    },
+
+    ChoiceStateEntered {
+        #[serde(rename = "stateEnteredEventDetails")]
+        details: StateEnteredEventDetails,
+    },
+    ChoiceStateExited {
+        #[serde(rename = "stateExitedEventDetails")]
+        details: StateExitedEventDetails,
+    },
+    FailStateEntered {
+        #[serde(rename = "stateEnteredEventDetails")]
+        details: StateEnteredEventDetails,
+    },
+    MapStateEntered {
+        #[serde(rename = "stateEnteredEventDetails")]
+        details: StateEnteredEventDetails,
+    },
+    MapStateExited {
+        #[serde(rename = "stateExitedEventDetails")]
+        details: StateExitedEventDetails,
+    },
+    ParallelStateEntered {
+        #[serde(rename = "stateEnteredEventDetails")]
+        details: StateEnteredEventDetails,
+    },
+    ParallelStateExited {
+        #[serde(rename = "stateExitedEventDetails")]
+        details: StateExitedEventDetails,
+    },
+    PassStateEntered {
+        #[serde(rename = "stateEnteredEventDetails")]
+        details: StateEnteredEventDetails,
+    },
+    PassStateExited {
+        #[serde(rename = "stateExitedEventDetails")]
+        details: StateExitedEventDetails,
+    },
+    SucceedStateEntered {
+        #[serde(rename = "stateEnteredEventDetails")]
+        details: StateEnteredEventDetails,
+    },
+    SucceedStateExited {
+        #[serde(rename = "stateExitedEventDetails")]
+        details: StateExitedEventDetails,
+    },
+    TaskStateEntered {
+        #[serde(rename = "stateEnteredEventDetails")]
+        details: StateEnteredEventDetails,
+    },
+    TaskStateExited {
+        #[serde(rename = "stateExitedEventDetails")]
+        details: StateExitedEventDetails,
+    },
+    TaskStateAborted,
+    WaitStateEntered {
+        #[serde(rename = "stateEnteredEventDetails")]
+        details: StateEnteredEventDetails,
+    },
+    WaitStateExited {
+        #[serde(rename = "stateExitedEventDetails")]
+        details: StateExitedEventDetails,
+    },
+    WaitStateAborted,
+
+    MapStateStarted {
+        #[serde(rename = "mapStateStartedEventDetails")]
+        details: MapStateStartedEventDetails,
+    },
+    MapStateSucceeded,
+    MapStateFailed,
+    MapStateAborted,
+    MapIterationStarted {
+        #[serde(rename = "mapIterationStartedEventDetails")]
+        details: MapIterationEventDetails,
+    },
+    MapIterationSucceeded {
+        #[serde(rename = "mapIterationSucceededEventDetails")]
+        details: MapIterationEventDetails,
+    },
+    MapIterationFailed {
+        #[serde(rename = "mapIterationFailedEventDetails")]
+        details: MapIterationEventDetails,
+    },
+    MapIterationAborted {
+        #[serde(rename = "mapIterationAbortedEventDetails")]
+        details: MapIterationEventDetails,
+    },
+    MapRunStarted {
+        #[serde(rename = "mapRunStartedEventDetails")]
+        details: MapRunStartedEventDetails,
+    },
+    MapRunSucceeded,
+    MapRunFailed {
+        #[serde(rename = "mapRunFailedEventDetails")]
+        details: FailureEventDetails,
+    },
+    MapRunAborted,
+    MapRunRedriven {
+        #[serde(rename = "mapRunRedrivenEventDetails")]
+        details: MapRunRedrivenEventDetails,
+    },
+
+    ParallelStateStarted,
+    ParallelStateSucceeded,
+    ParallelStateFailed,
+    ParallelStateAborted,
+
+    TaskScheduled {
+        #[serde(rename = "taskScheduledEventDetails")]
+        details: TaskScheduledEventDetails,
+    },
+    TaskStarted {
+        #[serde(rename = "taskStartedEventDetails")]
+        details: TaskStartedEventDetails,
+    },
+    TaskStartFailed {
+        #[serde(rename = "taskStartFailedEventDetails")]
+        details: TaskFailedEventDetails,
+    },
+    TaskSubmitted {
+        #[serde(rename = "taskSubmittedEventDetails")]
+        details: TaskSucceededEventDetails,
+    },
+    TaskSubmitFailed {
+        #[serde(rename = "taskSubmitFailedEventDetails")]
+        details: TaskFailedEventDetails,
+    },
+    TaskSucceeded {
+        #[serde(rename = "taskSucceededEventDetails")]
+        details: TaskSucceededEventDetails,
+    },
+    TaskFailed {
+        #[serde(rename = "taskFailedEventDetails")]
+        details: TaskFailedEventDetails,
+    },
+    TaskTimedOut {
+        #[serde(rename = "taskTimedOutEventDetails")]
+        details: TaskFailedEventDetails,
+    },
+
+    EvaluationFailed {
+        #[serde(rename = "evaluationFailedEventDetails")]
+        details: EvaluationFailedEventDetails,
+    },
+
+    /// An event type this server does not model, or one whose details did
+    /// not match the expected shape.
+    #[serde(untagged)]
+    Other {
+        #[serde(rename = "type")]
+        kind: String,
+        #[serde(flatten)]
+        fields: Map<String, Value>,
+    },
+}
+
+/// Whether `input` / `output` was cut short by the payload size limit.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct ExecutionDataDetails {
+    #[serde(default)]
+    pub truncated: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct FailureEventDetails {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cause: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct OutputEventDetails {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output: Option<String>,
+    #[serde(
+        rename = "outputDetails",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub output_details: Option<ExecutionDataDetails>,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct ExecutionStartedEventDetails {
+    #[serde(default)]
+    pub input: String,
+    #[serde(rename = "roleArn")]
+    pub role_arn: String,
+    #[serde(
+        rename = "inputDetails",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub input_details: Option<ExecutionDataDetails>,
+    #[serde(
+        rename = "stateMachineAliasArn",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub state_machine_alias_arn: Option<String>,
+    #[serde(
+        rename = "stateMachineVersionArn",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub state_machine_version_arn: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct ExecutionSucceededEventDetails {
+    #[serde(default)]
+    pub output: String,
+    #[serde(
+        rename = "outputDetails",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub output_details: Option<ExecutionDataDetails>,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct RedrivenEventDetails {
+    #[serde(rename = "redriveCount")]
+    pub redrive_count: u32,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct StateEnteredEventDetails {
+    pub name: String,
+    #[serde(default)]
+    pub input: String,
+    #[serde(
+        rename = "inputDetails",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub input_details: Option<ExecutionDataDetails>,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct StateExitedEventDetails {
+    pub name: String,
+    #[serde(default)]
+    pub output: String,
+    #[serde(
+        rename = "outputDetails",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub output_details: Option<ExecutionDataDetails>,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct LambdaFunctionScheduledEventDetails {
+    pub resource: String,
+    #[serde(default)]
+    pub input: String,
+    #[serde(
+        rename = "inputDetails",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub input_details: Option<ExecutionDataDetails>,
+    #[serde(
+        rename = "timeoutInSeconds",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub timeout_in_seconds: Option<u64>,
+    #[serde(
+        rename = "taskCredentials",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub task_credentials: Option<Value>,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct LambdaFunctionFailedEventDetails {
+    #[serde(default)]
+    pub error: String,
+    #[serde(default)]
+    pub cause: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct ActivityScheduledEventDetails {
+    pub resource: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub input: Option<String>,
+    #[serde(
+        rename = "inputDetails",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub input_details: Option<ExecutionDataDetails>,
+    #[serde(
+        rename = "timeoutInSeconds",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub timeout_in_seconds: Option<u64>,
+    #[serde(
+        rename = "heartbeatInSeconds",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub heartbeat_in_seconds: Option<u64>,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct ActivityStartedEventDetails {
+    #[serde(
+        rename = "workerName",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub worker_name: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct MapStateStartedEventDetails {
+    #[serde(default)]
+    pub length: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct MapIterationEventDetails {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub index: Option<u64>,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct MapRunStartedEventDetails {
+    #[serde(rename = "mapRunArn")]
+    pub map_run_arn: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct MapRunRedrivenEventDetails {
+    #[serde(rename = "mapRunArn")]
+    pub map_run_arn: String,
+    #[serde(rename = "redriveCount")]
+    pub redrive_count: u32,
+}
+
+/// A Task state calling a service integration, SDK or otherwise.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct TaskScheduledEventDetails {
+    #[serde(rename = "resourceType")]
+    pub resource_type: String,
+    pub resource: String,
+    pub region: String,
+    /// The JSON-encoded request the integration was called with.
+    pub parameters: String,
+    #[serde(
+        rename = "timeoutInSeconds",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub timeout_in_seconds: Option<u64>,
+    #[serde(
+        rename = "heartbeatInSeconds",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub heartbeat_in_seconds: Option<u64>,
+    #[serde(
+        rename = "taskCredentials",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub task_credentials: Option<Value>,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct TaskStartedEventDetails {
+    #[serde(rename = "resourceType")]
+    pub resource_type: String,
+    pub resource: String,
+}
+
+/// Shared by TaskStartFailed, TaskSubmitFailed, TaskFailed and TaskTimedOut.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct TaskFailedEventDetails {
+    #[serde(rename = "resourceType")]
+    pub resource_type: String,
+    pub resource: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cause: Option<String>,
+}
+
+/// Shared by TaskSubmitted and TaskSucceeded.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct TaskSucceededEventDetails {
+    #[serde(rename = "resourceType")]
+    pub resource_type: String,
+    pub resource: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output: Option<String>,
+    #[serde(
+        rename = "outputDetails",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub output_details: Option<ExecutionDataDetails>,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct EvaluationFailedEventDetails {
+    pub state: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cause: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub location: Option<String>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct EventResponse {
+    pub events: Vec<Event>,
+    /// Present when more events can be fetched with `?nextToken=`.
+    #[serde(rename = "nextToken", default, skip_serializing_if = "Option::is_none")]
+    pub next_token: Option<String>,
+}
+
+/// Query string of `GET /{region}/{arn}/history`.
+#[derive(Debug, Deserialize)]
+pub struct HistoryQuery {
+    #[serde(rename = "maxResults")]
+    pub max_results: Option<u32>,
+    /// Newest events first.
+    #[serde(rename = "reverseOrder", default)]
+    pub reverse_order: bool,
+    #[serde(rename = "nextToken")]
+    pub next_token: Option<String>,
+    /// `false` leaves out inputs and outputs, which can be large.
+    #[serde(rename = "includeExecutionData", default = "include_execution_data")]
+    pub include_execution_data: bool,
+}
+
+fn include_execution_data() -> bool {
+    true
+}
+
+impl Default for HistoryQuery {
+    fn default() -> Self {
+        HistoryQuery {
+            max_results: None,
+            reverse_order: false,
+            next_token: None,
+            include_execution_data: true,
+        }
+    }
+}
+
+/// How long one visit to a state took. States still running have no exit.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct StateDuration {
+    pub name: String,
+    #[serde(rename = "enteredEventId")]
+    pub entered_event_id: u64,
+    pub entered: Timestamp,
+    #[serde(rename = "exitedEventId", skip_serializing_if = "Option::is_none")]
+    pub exited_event_id: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exited: Option<Timestamp>,
+    #[serde(rename = "durationMillis", skip_serializing_if = "Option::is_none")]
+    pub duration_millis: Option<i64>,
+}
+
+#[derive(Serialize)]
+pub struct HistoryResponse {
+    #[serde(flatten)]
+    pub history: EventResponse,
+    #[serde(rename = "stateDurations")]
+    pub state_durations: Vec<StateDuration>,
+}
+
+/// Pairs every state exit with the entry it descends from through
+/// `previousEventId`, so interleaved Map iterations and Parallel branches each
+/// get their own span. Works on a single page of history too, in either
+/// direction; an exit whose entry is not on the page closes the oldest open
+/// entry of the same name.
+pub fn state_durations(events: &[Event]) -> Vec<StateDuration> {
+    let index = EventIndex::new(events);
+    let mut ordered: Vec<&Event> = events.iter().collect();
+    ordered.sort_by_key(|event| event.id);
+    let mut durations: Vec<StateDuration> = Vec::new();
+    let mut duration_of_entry: HashMap<u64, usize> = HashMap::new();
+    for event in ordered {
+        if let Some(entered) = event.state_entered() {
+            duration_of_entry.insert(event.id, durations.len());
+            durations.push(StateDuration {
+                name: entered.name.clone(),
+                entered_event_id: event.id,
+                entered: event.timestamp,
+                exited_event_id: None,
+                exited: None,
+                duration_millis: None,
+            });
+        } else if let Some(exited) = event.state_exited() {
+            let open = match index
+                .entered_state(event)
+                .and_then(|entered| duration_of_entry.get(&entered.id))
+            {
+                Some(&open) => durations.get_mut(open),
+                None => durations
+                    .iter_mut()
+                    .find(|open| open.exited.is_none() && open.name == exited.name),
+            };
+            if let Some(open) = open {
+                open.exited_event_id = Some(event.id);
+                open.exited = Some(event.timestamp);
+                open.duration_millis = Some(event.timestamp.millis_since(&open.entered));
+            }
+        }
+    }
+    durations
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn event(value: Value) -> Event {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn sdk_task_events_keep_their_payloads() {
+        let scheduled = event(json!({
+            "timestamp": "2024-03-01T10:00:00.000Z",
+            "type": "TaskScheduled",
+            "id": 3,
+            "previousEventId": 2,
+            "taskScheduledEventDetails": {
+                "resourceType": "dynamodb",
+                "resource": "putItem",
+                "region": "us-east-1",
+                "parameters": "{\"TableName\":\"payments\"}",
+                "timeoutInSeconds": 30
+            }
+        }));
+        let EventDetails::TaskScheduled { details } = &scheduled.details else {
+            panic!("expected TaskScheduled, got {:?}", scheduled.details);
+        };
+        assert_eq!(details.resource_type, "dynamodb");
+        assert_eq!(details.parameters, "{\"TableName\":\"payments\"}");
+        assert_eq!(details.timeout_in_seconds, Some(30));
+
+        let failed = event(json!({
+            "timestamp": "2024-03-01T10:00:00.000Z",
+            "type": "TaskFailed",
+            "id": 4,
+            "previousEventId": 3,
+            "taskFailedEventDetails": {
+                "resourceType": "dynamodb",
+                "resource": "putItem",
+                "error": "DynamoDB.ConditionalCheckFailedException",
+                "cause": "The conditional request failed"
+            }
+        }));
+        let EventDetails::TaskFailed { details } = &failed.details else {
+            panic!("expected TaskFailed, got {:?}", failed.details);
+        };
+        assert_eq!(
+            details.cause.as_deref(),
+            Some("The conditional request failed")
+        );
+    }
+
+    #[test]
+    fn execution_failures_and_map_iterations_are_typed() {
+        let failed = event(json!({
+            "timestamp": 1699612567.763,
+            "type": "ExecutionFailed",
+            "id": 9,
+            "executionFailedEventDetails": {"error": "States.Timeout"}
+        }));
+        assert_eq!(
+            failed.details,
+            EventDetails::ExecutionFailed {
+                details: FailureEventDetails {
+                    error: Some(String::from("States.Timeout")),
+                    cause: None,
+                },
+            }
+        );
+
+        let iteration = event(json!({
+            "timestamp": "2024-03-01T10:00:00.000Z",
+            "type": "MapIterationStarted",
+            "id": 5,
+            "mapIterationStartedEventDetails": {"name": "EachItem", "index": 2}
+        }));
+        assert_eq!(
+            iteration.details,
+            EventDetails::MapIterationStarted {
+                details: MapIterationEventDetails {
+                    name: Some(String::from("EachItem")),
+                    index: Some(2),
+                },
+            }
+        );
+
+        let started = event(json!({
+            "timestamp": "2024-03-01T10:00:00.000Z",
+            "type": "ParallelStateStarted",
+            "id": 6,
+            "previousEventId": 5
+        }));
+        assert_eq!(started.details, EventDetails::ParallelStateStarted);
+
+        let aborted = event(json!({
+            "timestamp": "2024-03-01T10:00:00.000Z",
+            "type": "TaskStateAborted",
+            "id": 7,
+            "previousEventId": 6
+        }));
+        assert_eq!(aborted.details, EventDetails::TaskStateAborted);
+        assert!(aborted.is_state_aborted());
+    }
+
+    #[test]
+    fn unknown_event_types_are_kept_verbatim() {
+        let original = json!({
+            "timestamp": "2024-03-01T10:00:00.000Z",
+            "type": "SomethingNew",
+            "id": 7,
+            "previousEventId": 6,
+            "somethingNewEventDetails": {"answer": 42}
+        });
+        let parsed = event(original.clone());
+        let EventDetails::Other { kind, fields } = &parsed.details else {
+            panic!("expected Other, got {:?}", parsed.details);
+        };
+        assert_eq!(kind, "SomethingNew");
+        assert_eq!(fields["somethingNewEventDetails"], json!({"answer": 42}));
+
+        let serialized = serde_json::to_value(&parsed).unwrap();
+        assert_eq!(serialized["type"], "SomethingNew");
+        assert_eq!(
+            serialized["somethingNewEventDetails"],
+            json!({"answer": 42})
+        );
+    }
+
+    #[test]
+    fn long_histories_keep_their_links() {
+        let parsed = event(json!({
+            "timestamp": "2024-03-01T10:00:00.000Z",
+            "type": "PassStateEntered",
+            "id": 70001,
+            "previousEventId": 70000,
+            "stateEnteredEventDetails": {"name": "Again", "input": "{}"}
+        }));
+        assert_eq!(parsed.previous_event_id, Some(70000));
+        assert_eq!(
+            serde_json::to_value(&parsed).unwrap()["previousEventId"],
+            70000
+        );
+    }
+
+    #[test]
+    fn state_durations_pair_entries_with_exits() {
+        let events: Vec<Event> = serde_json::from_value(json!([
+            {"timestamp": 1700000000.0, "type": "ExecutionStarted", "id": 1,
+             "executionStartedEventDetails": {"input": "{}", "roleArn": "arn:role"}},
+            {"timestamp": 1700000000.1, "type": "MapStateEntered", "id": 2, "previousEventId": 1,
+             "stateEnteredEventDetails": {"name": "Each", "input": "[1,2]"}},
+            {"timestamp": 1700000000.1, "type": "MapIterationStarted", "id": 3, "previousEventId": 2,
+             "mapIterationStartedEventDetails": {"name": "Each", "index": 0}},
+            {"timestamp": 1700000000.1, "type": "MapIterationStarted", "id": 4, "previousEventId": 2,
+             "mapIterationStartedEventDetails": {"name": "Each", "index": 1}},
+            {"timestamp": 1700000000.2, "type": "TaskStateEntered", "id": 5, "previousEventId": 3,
+             "stateEnteredEventDetails": {"name": "Work", "input": "1"}},
+            {"timestamp": 1700000000.25, "type": "TaskStateEntered", "id": 6, "previousEventId": 4,
+             "stateEnteredEventDetails": {"name": "Work", "input": "2"}},
+            {"timestamp": 1700000000.7, "type": "TaskStateExited", "id": 7, "previousEventId": 6,
+             "stateExitedEventDetails": {"name": "Work", "output": "2"}},
+            {"timestamp": 1700000001.0, "type": "TaskStateExited", "id": 8, "previousEventId": 5,
+             "stateExitedEventDetails": {"name": "Work", "output": "1"}}
+        ]))
+        .unwrap();
+
+        let mut reversed = events.clone();
+        reversed.reverse();
+        assert_eq!(state_durations(&reversed), state_durations(&events));
+
+        let durations = state_durations(&events);
+        let spans: Vec<(&str, u64, Option<u64>, Option<i64>)> = durations
+            .iter()
+            .map(|span| {
+                (
+                    span.name.as_str(),
+                    span.entered_event_id,
+                    span.exited_event_id,
+                    span.duration_millis,
+                )
+            })
+            .collect();
+        assert_eq!(
+            spans,
+            [
+                ("Each", 2, None, None),
+                ("Work", 5, Some(8), Some(800)),
+                ("Work", 6, Some(7), Some(450)),
+            ]
+        );
+    }
+
+    #[test]
+    fn serialized_events_keep_the_shape_the_frontend_decodes() {
+        let entered = event(json!({
+            "timestamp": "2024-03-01T10:00:00.000Z",
+            "type": "TaskStateEntered",
+            "id": 2,
+            "previousEventId": 1,
+            "stateEnteredEventDetails": {"name": "ChargeCard", "input": "{}"}
+        }));
+        assert_eq!(
+            serde_json::to_value(&entered).unwrap(),
+            json!({
+                "timestamp": "2024-03-01T10:00:00.000Z",
+                "type": "TaskStateEntered",
+                "id": 2,
+                "previousEventId": 1,
+                "stateEnteredEventDetails": {"name": "ChargeCard", "input": "{}"},
+                "executionStartedEventDetails": null,
+                "executionSucceededEventDetails": null,
+                "lambdaFunctionFailedEventDetails": null,
+                "lambdaFunctionScheduledEventDetails": null,
+                "stateExitedEventDetails": null
+            })
+        );
+    }
+}
@@ -1,330 +1,1699 @@
-use crate::model:: {
-      EventResponse, ExecutionsResponse, ServerError, StateMachine, StateMachineResponse, StateMachineDefinition
+use crate::asl::graph::{DefinitionGraph, Graph};
+use crate::backend::{BackendError, Bounded, CliBackend, HttpBackend, StepFunctions};
+use crate::config::{BackendKind, Config};
+use crate::error::ApiError;
+use crate::history::{Event, HistoryQuery, HistoryResponse};
+use crate::model::{
+    BulkDeleteRequest, BulkItem, BulkResponse, BulkStopRequest, CauseChainQuery,
+    CreateStateMachineRequest, DeleteStateMachineQuery, DeleteStateMachineResponse, Executions,
+    ExecutionsQuery, RerunExecutionResponse, StartExecutionRequest, StateMachineUpdate,
+    StopExecutionRequest, UpdateStateMachineRequest,
 };
+use crate::timestamp::Timestamp;
+use crate::validate::{ArnKind, ValidationError};
 use actix_cors::Cors;
 use actix_web::http::header::ContentType;
-use actix_web::{  delete, get, http, post, web, App, HttpRequest, HttpResponse, HttpServer};
-use std::process::Command;
-use std::str;
+use actix_web::http::StatusCode;
+use actix_web::ResponseError;
+use actix_web::{
+    delete, get, http, post, put, routes, web, App, HttpRequest, HttpResponse, HttpServer,
+};
+use serde_json::Value;
+use std::future::Future;
+use std::sync::Arc;
+use tokio::task::JoinSet;
 
+mod asl;
+mod backend;
+mod causes;
+mod config;
+mod diff;
+mod error;
+mod groups;
+mod history;
+mod merge_patch;
 mod model;
-
-const PORT: u16 = 6969;
+mod steps;
+mod timestamp;
+mod trace;
+mod validate;
 
 #[get("/{region}/state-machines")]
-async fn get_state_machines(region: web::Path<String>) -> HttpResponse {
+async fn get_state_machines(
+    region: web::Path<String>,
+    client: web::Data<dyn StepFunctions>,
+) -> Result<HttpResponse, ApiError> {
+    let region = validate::region(&region)?;
     println!("[STATE MACHINES]: {}", region);
 
-    return match Command::new("sh")
-        .arg("-c")
-        .arg(format!("aws stepfunctions list-state-machines --endpoint-url http://localhost:8083 --region {}", region.as_str()))
-        .output() {
-        Ok(o) => {
-            println!("[STATE MACHINES]: {:?}", o);
-            if o.status.success() {
-                match str::from_utf8(&o.stdout) {
-                    Ok(o) => {
-                        match serde_json::from_str::<StateMachineResponse>(o) {
-                            Ok(machines) => {
-                                HttpResponse::Ok()
-                                    .content_type(ContentType::json())
-                                    .json(machines)
-                            },
-                            Err(e) => {
-                                HttpResponse::InternalServerError()
-                                    .content_type(ContentType::json())
-                                    .json(ServerError {message: format!("ERROR: Failed to parse state machines. {:?}", e)})
-                            }
-                        }
-                    },
-                    Err(err) => {
-                        HttpResponse::InternalServerError()
-                            .content_type(ContentType::json())
-                            .json(ServerError {message: format!("ERROR: Output convert failed due to: {err}")})
-                    }
-                }
-            } else {
-                HttpResponse::InternalServerError()
-                    .content_type(ContentType::json())
-                    .json(ServerError {message: format!("ERROR: Step Function exited with status code: {}", o.status.code().unwrap())})
-            }
-        },
-        Err(e) => {
-            HttpResponse::InternalServerError()
-                .content_type(ContentType::json())
-                .json(ServerError {message: format!("ERROR: Executing Step Function CLI command. {:?}", e)})
-        }
-    };
+    let machines = client.list_state_machines(&region).await.map_err(|e| {
+        println!("[STATE MACHINES]: {}", e);
+        ApiError::backend("ListStateMachines", e)
+    })?;
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .json(machines))
 }
 
 #[get("/{region}/{arn}/state-machine")]
-async fn get_state_machine(req: HttpRequest) -> HttpResponse {
-    let region: String = req.match_info().get("region").unwrap().parse().unwrap();
-    let arn: String = req.match_info().get("arn").unwrap().parse().unwrap();
+async fn get_state_machine(
+    req: HttpRequest,
+    client: web::Data<dyn StepFunctions>,
+) -> Result<HttpResponse, ApiError> {
+    let (region, arn) = validate::target(&req, ArnKind::StateMachine)?;
     println!("[STATE MACHINE]: {}, {}", region, arn);
-    return match Command::new("sh")
-        .arg("-c")
-        .arg(format!("aws stepfunctions describe-state-machine --endpoint-url http://localhost:8083 --region {} --state-machine-arn {}", region.as_str(), arn.as_str()))
-        .output() {
-        Ok(o) => {
-            println!("[STATE MACHINE]: {:?}", o);
-            if o.status.success() {
-                match str::from_utf8(&o.stdout) {
-                    Ok(o) => {
-                        match serde_json::from_str::<StateMachine>(o) {
-                            Ok(machine) => {
-                                HttpResponse::Ok()
-                                    .content_type(ContentType::json())
-                                    .json(machine)
-                            },
-                            Err(e) => {
-                                HttpResponse::InternalServerError()
-                                    .content_type(ContentType::json())
-                                    .json(ServerError {message: format!("ERROR: Failed to parse state machine. {:?}", e)})
-                            }
-                        }
-                    },
-                    Err(err) => {
-                        HttpResponse::InternalServerError()
-                            .content_type(ContentType::json())
-                            .json(ServerError {message: format!("ERROR: Output convert failed due to: {err}")})
-                    }
-                }
-            } else {
-                HttpResponse::InternalServerError()
-                    .content_type(ContentType::json())
-                    .json(ServerError {message: format!("ERROR: Step Function exited with status code: {}", o.status.code().unwrap())})
-            }
-        },
-        Err(e) => {
-            HttpResponse::InternalServerError()
-                .content_type(ContentType::json())
-                .json(ServerError {message: format!("ERROR: Executing Step Function CLI \"describe-state-machine\" command. {:?}", e)})
-        }
-    };
+
+    let machine = client
+        .describe_state_machine(&region, &arn)
+        .await
+        .map_err(|e| {
+            println!("[STATE MACHINE]: {}", e);
+            ApiError::backend("DescribeStateMachine", e)
+        })?;
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .json(machine))
 }
 
 #[get("/{region}/{arn}/executions")]
-async fn get_executions(req: HttpRequest) -> HttpResponse {
-    let region: String = req.match_info().get("region").unwrap().parse().unwrap();
-    let arn: String = req.match_info().get("arn").unwrap().parse().unwrap();
+async fn get_executions(
+    req: HttpRequest,
+    client: web::Data<dyn StepFunctions>,
+) -> Result<HttpResponse, ApiError> {
+    let (region, arn) = validate::target(&req, ArnKind::StateMachine)?;
+    let query = validate::executions_query(&req)?;
     println!("[EXECUTIONS]: {}, {}", region, arn);
-    match Command::new("sh")
-        .arg("-c")
-        .arg(format!("aws stepfunctions list-executions --endpoint-url http://localhost:8083 --region {} --no-paginate --state-machine-arn {}", region, arn))
-        .output() {
-        Ok(o) => {
-            println!("[EXECUTIONS]: {:?}", o);
-            if o.status.success() {
-                match str::from_utf8(&o.stdout) {
-                    Ok(output) => {
-                        match serde_json::from_str::<ExecutionsResponse>(output) {
-                            Ok(executions) => {
-                                HttpResponse::Ok()
-                                    .content_type(ContentType::json())
-                                    .json(executions)
-                            },
-                            Err(e) => {
-                                HttpResponse::InternalServerError()
-                                    .content_type(ContentType::json())
-                                    .json(ServerError {message: format!("ERROR: Failed to parse executions. {:?}", e)})
-                            }
-                        }
-                    }
-                    Err(error) => HttpResponse::InternalServerError()
-                        .content_type(ContentType::json())
-                        .json(ServerError {message: format!("ERROR: Output convert failed due to: {error}")})
-                }
-            } else {
-                HttpResponse::InternalServerError()
-                    .content_type(ContentType::json())
-                    .json(ServerError {message: format!("ERROR: Step Function exited with status code: {}", o.status.code().unwrap())})
-            }
-        }
-        Err(error) => {
-            HttpResponse::InternalServerError()
-                .content_type(ContentType::json())
-                .json(ServerError { message: format!("ERROR: Executing Step Function CLI \"list-executions\" failed due to {:?}", error) })
-        }
+
+    let mut executions = client
+        .list_executions(&region, &arn, &query)
+        .await
+        .map_err(|e| {
+            println!("[EXECUTIONS]: {}", e);
+            ApiError::backend("ListExecutions", e)
+        })?;
+    for listed in &mut executions.executions {
+        listed.duration_millis = listed.duration_millis();
     }
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .json(executions))
 }
 
 #[get("/{region}/{arn}/history")]
-async fn execution(req: HttpRequest) -> HttpResponse {
-    let region: String = req.match_info().get("region").unwrap().parse().unwrap();
-    let arn: String = req.match_info().get("arn").unwrap().parse().unwrap();
+async fn execution(
+    req: HttpRequest,
+    client: web::Data<dyn StepFunctions>,
+) -> Result<HttpResponse, ApiError> {
+    let (region, arn) = validate::target(&req, ArnKind::Execution)?;
+    let query = validate::history_query(&req)?;
     println!("[EXECUTION HISTORY]: {}, {}", region, arn);
-    match Command::new("sh")
-        .arg("-c")
-        .arg(format!("aws stepfunctions get-execution-history --endpoint-url http://localhost:8083 --region {} --no-paginate --execution-arn {}", region, arn))
-        .output() {
-        Ok(o) => {
-            println!("[EXECUTION HISTORY]: {:?}", o);
-            if o.status.success() {
-                match str::from_utf8(&o.stdout) {
-                    Ok(output) => {
-                        match serde_json::from_str::<EventResponse>(output) {
-                            Ok(executions) => {
-                                HttpResponse::Ok()
-                                    .content_type(ContentType::json())
-                                    .json(executions)
-                            },
-                            Err(e) => {
-                                HttpResponse::InternalServerError()
-                                    .content_type(ContentType::json())
-                                    .json(ServerError {message: format!("ERROR: Failed to parse execution events. {:?}", e)})
-                            }
-                        }
-                    }
-                    Err(error) => HttpResponse::InternalServerError()
-                        .content_type(ContentType::json())
-                        .json(ServerError {message: format!("ERROR: Output convert failed due to: {error}")})
-                }
-            } else {
-                HttpResponse::InternalServerError()
-                    .content_type(ContentType::json())
-                    .json(ServerError {message: format!("ERROR: Step Function exited with status code: {}", o.status.code().unwrap())})
-            }
 
+    let history = client
+        .get_execution_history(&region, &arn, &query)
+        .await
+        .map_err(|e| {
+            println!("[EXECUTION HISTORY]: {}", e);
+            ApiError::backend("GetExecutionHistory", e)
+        })?;
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .json(HistoryResponse {
+            state_durations: history::state_durations(&history.events),
+            history,
+        }))
+}
+
+const DEFAULT_ROLE_ARN: &str = "arn:aws:iam::123456789012:role/DummyRole";
+
+#[post("/{region}/state-machines")]
+async fn create_state_machine(
+    region: web::Path<String>,
+    body: web::Bytes,
+    client: web::Data<dyn StepFunctions>,
+) -> Result<HttpResponse, ApiError> {
+    let region = validate::region(&region)?;
+    let request: CreateStateMachineRequest = validate::json_body(&body)?;
+    let name = validate::resource_name(&request.name)?;
+    let definition = validate::definition(request.definition)?;
+    let kind = match request.kind.as_deref() {
+        None | Some("STANDARD") => "STANDARD",
+        Some("EXPRESS") => "EXPRESS",
+        Some(kind) => {
+            return Err(ValidationError::RequestBody(format!(
+                "type must be STANDARD or EXPRESS, got {kind:?}"
+            ))
+            .into())
         }
-        Err(error) => {
-            HttpResponse::InternalServerError()
-                .content_type(ContentType::json())
-                .json(ServerError { message: format!("ERROR: Executing Step Function CLI \"get-execution-history\" failed due to {:?}", error) })
+    };
+    let role_arn = request
+        .role_arn
+        .unwrap_or_else(|| String::from(DEFAULT_ROLE_ARN));
+
+    println!("[CREATE STATE MACHINE]: {} {}", region, name);
+
+    let created = client
+        .create_state_machine(&region, &name, &definition, &role_arn, kind)
+        .await
+        .map_err(|e| {
+            println!("[CREATE STATE MACHINE]: {}", e);
+            ApiError::backend("CreateStateMachine", e)
+        })?;
+    Ok(HttpResponse::Created()
+        .content_type(ContentType::json())
+        .json(created))
+}
+
+#[put("/{region}/{arn}/state-machine")]
+async fn update_state_machine(
+    req: HttpRequest,
+    body: web::Bytes,
+    client: web::Data<dyn StepFunctions>,
+) -> Result<HttpResponse, ApiError> {
+    let (region, arn) = validate::target(&req, ArnKind::StateMachine)?;
+    let request: UpdateStateMachineRequest = validate::json_body(&body)?;
+    let definition = validate::definition(request.definition)?;
+
+    println!("[UPDATE STATE MACHINE]: {} {}", region, arn);
+
+    let previous = client
+        .describe_state_machine(&region, &arn)
+        .await
+        .map_err(|e| ApiError::backend("DescribeStateMachine", e))?
+        .definition
+        .and_then(|definition| serde_json::from_str::<Value>(&definition).ok())
+        .unwrap_or(Value::Null);
+
+    let updated = client
+        .update_state_machine(&region, &arn, &definition)
+        .await
+        .map_err(|e| {
+            println!("[UPDATE STATE MACHINE]: {}", e);
+            ApiError::backend("UpdateStateMachine", e)
+        })?;
+    let submitted = serde_json::from_str::<Value>(&definition).unwrap_or(Value::Null);
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .json(StateMachineUpdate {
+            updated,
+            diff: diff::diff(&previous, &submitted),
+        }))
+}
+
+/// Every running execution of a state machine, following pages.
+async fn running_executions(
+    client: &dyn StepFunctions,
+    region: &str,
+    arn: &str,
+) -> Result<Vec<Executions>, BackendError> {
+    let mut query = ExecutionsQuery {
+        status: Some(String::from("RUNNING")),
+        limit: Some(validate::MAX_PAGE_SIZE),
+        next_token: None,
+    };
+    let mut running = Vec::new();
+    loop {
+        let page = client.list_executions(region, arn, &query).await?;
+        running.extend(page.executions);
+        match page.next_token {
+            Some(token) => query.next_token = Some(token),
+            None => return Ok(running),
         }
     }
 }
 
+/// Shared by the single and bulk delete routes.
+async fn delete_one(
+    client: &dyn StepFunctions,
+    region: &str,
+    arn: String,
+    dry_run: bool,
+    force: bool,
+) -> Result<DeleteStateMachineResponse, ApiError> {
+    let running = running_executions(client, region, &arn)
+        .await
+        .map_err(|e| ApiError::backend("ListExecutions", e))?
+        .len();
+    if running > 0 && !force && !dry_run {
+        return Err(ApiError::RunningExecutions {
+            arn,
+            count: running,
+        });
+    }
+    if !dry_run {
+        client
+            .delete_state_machine(region, &arn)
+            .await
+            .map_err(|e| {
+                println!("[DELETE STATE MACHINE]: {}", e);
+                ApiError::backend("DeleteStateMachine", e)
+            })?;
+    }
+    Ok(DeleteStateMachineResponse {
+        state_machine_arn: arn,
+        running_executions: running,
+        dry_run,
+        deleted: !dry_run,
+    })
+}
+
+/// Deletes a state machine. `?dryRun=true` only reports how many executions
+/// are running; running executions block the delete unless `?force=true`.
 #[delete("/{region}/{arn}/state-machine")]
-async fn delete_state_machine(req: HttpRequest) -> HttpResponse {
-    let region: String = req.match_info().get("region").unwrap().parse().unwrap();
-    let arn: String = req.match_info().get("arn").unwrap().parse().unwrap();
-    println!("[DELETE STATE MACHINE]: {} {}", region, arn);
-    return match Command::new("sh")
-        .arg("-c")
-        .arg(format!("aws stepfunctions delete-state-machine --endpoint-url http://localhost:8083 --region {} --state-machine-arn {}", region.as_str(), arn.as_str()))
-        .output() {
-        Ok(o) => {
-            println!("[DELETE STATE MACHINE]: {:?}", o.status.success());
-            HttpResponse::Ok()
-                .content_type(ContentType::json())
-                .json(0)
+async fn delete_state_machine(
+    req: HttpRequest,
+    client: web::Data<dyn StepFunctions>,
+) -> Result<HttpResponse, ApiError> {
+    let (region, arn) = validate::target(&req, ArnKind::StateMachine)?;
+    let query: DeleteStateMachineQuery = validate::query(&req)?;
+    println!("[DELETE STATE MACHINE]: {} {} {:?}", region, arn, query);
+
+    let deleted = delete_one(client.as_ref(), &region, arn, query.dry_run, query.force).await?;
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .json(deleted))
+}
+
+/// Runs `operation` on every ARN at once and collects the outcomes in input
+/// order. The backend's concurrency cap still applies to the calls it makes.
+async fn fan_out<T, F, Fut>(arns: Vec<String>, operation: F) -> BulkResponse<T>
+where
+    T: Send + 'static,
+    F: Fn(String) -> Fut,
+    Fut: Future<Output = Result<T, ApiError>> + Send + 'static,
+{
+    let mut tasks = JoinSet::new();
+    for (index, arn) in arns.iter().enumerate() {
+        let outcome = operation(arn.clone());
+        tasks.spawn(async move { (index, outcome.await) });
+    }
+    let mut outcomes: Vec<Option<Result<T, ApiError>>> = arns.iter().map(|_| None).collect();
+    while let Some(joined) = tasks.join_next().await {
+        match joined {
+            Ok((index, outcome)) => outcomes[index] = Some(outcome),
+            Err(e) => println!("[BULK]: {}", e),
         }
-        Err(e) => {
-            HttpResponse::InternalServerError()
-                .content_type(ContentType::json())
-                .json(ServerError {message: format!("ERROR: Executing Step Function CLI \"delete-state-machine\" command. {:?}", e)})
+    }
+
+    let results: Vec<BulkItem<T>> = arns
+        .into_iter()
+        .zip(outcomes)
+        .map(|(arn, outcome)| {
+            let outcome = outcome.unwrap_or_else(|| {
+                Err(ApiError::Internal(String::from(
+                    "The operation did not finish.",
+                )))
+            });
+            match outcome {
+                Ok(result) => BulkItem {
+                    arn,
+                    status: StatusCode::OK.as_u16(),
+                    result: Some(result),
+                    error: None,
+                },
+                Err(e) => BulkItem {
+                    arn,
+                    status: e.status_code().as_u16(),
+                    result: None,
+                    error: Some(e.body()),
+                },
+            }
+        })
+        .collect();
+    let succeeded = results.iter().filter(|item| item.result.is_some()).count();
+    BulkResponse {
+        failed: results.len() - succeeded,
+        succeeded,
+        results,
+    }
+}
+
+/// Stops every running execution of a state machine, or only those whose
+/// name starts with `namePrefix`.
+#[post("/{region}/{arn}/stop-executions")]
+async fn stop_executions(
+    req: HttpRequest,
+    body: web::Bytes,
+    client: web::Data<dyn StepFunctions>,
+) -> Result<HttpResponse, ApiError> {
+    let (region, arn) = validate::target(&req, ArnKind::StateMachine)?;
+    let request: BulkStopRequest = validate::json_body(&body)?;
+    let (error, cause) = validate::stop_reason(request.error, request.cause)?;
+    let prefix = request.name_prefix.unwrap_or_default();
+
+    println!("[STOP EXECUTIONS]: {} {} {:?}", region, arn, prefix);
+
+    let targets: Vec<String> = running_executions(client.as_ref(), &region, &arn)
+        .await
+        .map_err(|e| ApiError::backend("ListExecutions", e))?
+        .into_iter()
+        .filter(|running| running.name.starts_with(&prefix))
+        .map(|running| running.execution_arn)
+        .collect();
+
+    let client = client.into_inner();
+    let stopped = fan_out(targets, |execution_arn| {
+        let (client, region, error, cause) =
+            (client.clone(), region.clone(), error.clone(), cause.clone());
+        async move {
+            client
+                .stop_execution(&region, &execution_arn, &error, &cause)
+                .await
+                .map_err(|e| ApiError::backend("StopExecution", e))
         }
-    };
+    })
+    .await;
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .json(stopped))
 }
 
-#[post("/{region}/{arn}/stop-execution")]
-async fn stop_execution(req: HttpRequest) -> HttpResponse {
-    let region: String = req.match_info().get("region").unwrap().parse().unwrap();
-    let arn: String = req.match_info().get("arn").unwrap().parse().unwrap();
-
-    println!("[STOP EXECUTION]: {} {}", region, arn);
-
-    return match Command::new("sh")
-        .arg("-c")
-        .arg(format!("aws stepfunctions stop-execution --endpoint-url http://localhost:8083 --region {} --execution-arn {} --cause \"manual step functions local stop\" --error manualstop", region.as_str(), arn.as_str()))
-        .output() {
-        Ok(o) => {
-            println!("[STOP EXECUTION]: {:?}", o);
-            if o.status.success() {
-                HttpResponse::Ok()
-                    .content_type(ContentType::json())
-                    .json(0)
-            } else {
-                HttpResponse::InternalServerError()
-                    .content_type(ContentType::json())
-                    .json(ServerError {message: format!("ERROR: Executing Step Function CLI \"stop-execution\" command. {:?}", o.status)})
-            }
+/// Deletes several state machines; each ARN succeeds or fails on its own.
+#[post("/{region}/state-machines/delete")]
+async fn delete_state_machines(
+    region: web::Path<String>,
+    body: web::Bytes,
+    client: web::Data<dyn StepFunctions>,
+) -> Result<HttpResponse, ApiError> {
+    let region = validate::region(&region)?;
+    let request: BulkDeleteRequest = validate::json_body(&body)?;
+    if request.state_machine_arns.is_empty() {
+        return Err(ValidationError::RequestBody(String::from("stateMachineArns is empty")).into());
+    }
+
+    println!(
+        "[DELETE STATE MACHINES]: {} {:?}",
+        region, request.state_machine_arns
+    );
+
+    let client = client.into_inner();
+    let deleted = fan_out(request.state_machine_arns, |arn| {
+        let (client, region) = (client.clone(), region.clone());
+        async move {
+            let arn = validate::arn(&arn, ArnKind::StateMachine)?;
+            delete_one(
+                client.as_ref(),
+                &region,
+                arn,
+                request.dry_run,
+                request.force,
+            )
+            .await
         }
-        Err(e) => {
-            HttpResponse::InternalServerError()
-                .content_type(ContentType::json())
-                .json(ServerError {message: format!("ERROR: Executing Step Function CLI \"stop-execution\" command. {:?}", e)})
+    })
+    .await;
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .json(deleted))
+}
+
+/// Stops a running execution. The optional body sets the `error` and `cause`
+/// recorded on it, so Catch and notification logic can be exercised.
+#[post("/{region}/{arn}/stop-execution")]
+async fn stop_execution(
+    req: HttpRequest,
+    body: web::Bytes,
+    client: web::Data<dyn StepFunctions>,
+) -> Result<HttpResponse, ApiError> {
+    let (region, arn) = validate::target(&req, ArnKind::Execution)?;
+    let request: StopExecutionRequest = validate::json_body(&body)?;
+    let (error, cause) = validate::stop_reason(request.error, request.cause)?;
+
+    println!("[STOP EXECUTION]: {} {} {}", region, arn, error);
+
+    let stopped = client
+        .stop_execution(&region, &arn, &error, &cause)
+        .await
+        .map_err(|e| {
+            println!("[STOP EXECUTION]: {}", e);
+            ApiError::backend("StopExecution", e)
+        })?;
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .json(stopped))
+}
+
+#[post("/{region}/{arn}/start-execution")]
+async fn start_execution(
+    req: HttpRequest,
+    body: web::Bytes,
+    client: web::Data<dyn StepFunctions>,
+) -> Result<HttpResponse, ApiError> {
+    let (region, arn) = validate::target(&req, ArnKind::StateMachine)?;
+    let request: StartExecutionRequest = validate::json_body(&body)?;
+    let name = request
+        .name
+        .as_deref()
+        .map(validate::resource_name)
+        .transpose()?;
+    let input = validate::execution_input(request.input)?;
+
+    println!("[START EXECUTION]: {} {} {:?}", region, arn, name);
+
+    let started = client
+        .start_execution(&region, &arn, name.as_deref(), &input)
+        .await
+        .map_err(|e| {
+            println!("[START EXECUTION]: {}", e);
+            ApiError::backend("StartExecution", e)
+        })?;
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .json(started))
+}
+
+/// Starts a new execution of the same state machine with the original input,
+/// optionally edited by a JSON merge patch sent as the request body. An empty
+/// body reruns the original input; a `null` body is a patch like any other and
+/// replaces the input with `null`, as RFC 7396 says.
+#[post("/{region}/{arn}/rerun")]
+async fn rerun_execution(
+    req: HttpRequest,
+    body: web::Bytes,
+    client: web::Data<dyn StepFunctions>,
+) -> Result<HttpResponse, ApiError> {
+    let (region, arn) = validate::target(&req, ArnKind::Execution)?;
+    // Not `validate::json_body`, which reads `null` as "nothing sent".
+    let patch = match body.trim_ascii() {
+        b"" => None,
+        body => Some(
+            serde_json::from_slice::<Value>(body)
+                .map_err(|e| ValidationError::RequestBody(e.to_string()))?,
+        ),
+    };
+
+    println!("[RERUN EXECUTION]: {} {}", region, arn);
+
+    // ExecutionStarted is always the first event.
+    let first = HistoryQuery {
+        max_results: Some(1),
+        ..HistoryQuery::default()
+    };
+    let history = client
+        .get_execution_history(&region, &arn, &first)
+        .await
+        .map_err(|e| ApiError::backend("GetExecutionHistory", e))?;
+    let Some(original_input) = history
+        .events
+        .iter()
+        .find_map(|event| event.execution_started())
+        .map(|details| details.input.as_str())
+    else {
+        return Err(ApiError::Internal(format!(
+            "Execution {} has no ExecutionStarted event.",
+            arn
+        )));
+    };
+
+    let input = match patch {
+        None => original_input.to_string(),
+        Some(patch) => {
+            let mut input = serde_json::from_str::<Value>(original_input).map_err(|e| {
+                ApiError::Internal(format!("Original input of {} is not JSON. {}", arn, e))
+            })?;
+            merge_patch::apply(&mut input, &patch);
+            input.to_string()
         }
     };
+
+    let machine = client
+        .describe_state_machine_for_execution(&region, &arn)
+        .await
+        .map_err(|e| ApiError::backend("DescribeStateMachineForExecution", e))?;
+
+    let started = client
+        .start_execution(&region, &machine.state_machine_arn, None, &input)
+        .await
+        .map_err(|e| {
+            println!("[RERUN EXECUTION]: {}", e);
+            ApiError::backend("StartExecution", e)
+        })?;
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .json(RerunExecutionResponse {
+            started,
+            original_history: format!("/{}/{}/history", region, arn),
+            original_execution_arn: arn,
+        }))
 }
 
-#[get("/{region}/{arn}/describe")]
-async fn describe_execution(req: HttpRequest) -> HttpResponse {
-    let region: String = req.match_info().get("region").unwrap().parse().unwrap();
-    let arn: String = req.match_info().get("arn").unwrap().parse().unwrap();
+/// Status, payloads and timing of one execution.
+#[get("/{region}/{arn}/execution")]
+async fn describe_execution(
+    req: HttpRequest,
+    client: web::Data<dyn StepFunctions>,
+) -> Result<HttpResponse, ApiError> {
+    let (region, arn) = validate::target(&req, ArnKind::Execution)?;
 
     println!("[DESCRIBE EXECUTION]: {} {}", region, arn);
 
-    return match Command::new("sh")
-        .arg("-c")
-        .arg(format!("aws stepfunctions describe-state-machine-for-execution --endpoint-url http://localhost:8083 --region {} --execution-arn {}", region.as_str(), arn.as_str()))
-        .output() {
-            Ok(o) => {
-                println!("[DESCRIBE EXECUTION]: {:?}", o);
-
-                if o.status.success() {
-                    match str::from_utf8(&o.stdout) {
-                        Ok(output) => {
-                            match serde_json::from_str::<model::StateMachineDescriptor>(output) {
-                                Ok(o) => {
-                                    match serde_json::from_str::<StateMachineDefinition>(&o.definition) {
-                                        Ok(definition) => 
-                                            HttpResponse::Ok()
-                                                .content_type(ContentType::json())
-                                                .json(definition),
-                                        Err(e) => 
-                                            HttpResponse::InternalServerError()
-                                                .content_type(ContentType::json())
-                                                .json(ServerError {message: format!("ERROR: Failed to parse step machine definition. {:?}", e)})
-                                    }
-                                },
-                                Err(e) => 
-                                    HttpResponse::InternalServerError()
-                                        .content_type(ContentType::json())
-                                        .json(ServerError {message: format!("ERROR: Failed to parse step machine descriptor response. {:?}", e)})
-                            }
-                        },
-                        Err(error) => HttpResponse::InternalServerError()
-                            .content_type(ContentType::json())
-                            .json(ServerError {message: format!("ERROR: Output convert failed due to: {error}")})
-                    }
-                } else {
-                    HttpResponse::InternalServerError()
-                        .content_type(ContentType::json())
-                        .json(ServerError {message: format!("ERROR: Step Function exited with status code: {}", o.status.code().unwrap())})
-                }
-            }
-            Err(e) => 
-                HttpResponse::InternalServerError()
-                    .content_type(ContentType::json())
-                    .json(ServerError {message: format!("ERROR: Executing Step Function CLI \"describe-state-machine-for-execution\" command. {:?}", e)})
+    let mut description = client
+        .describe_execution(&region, &arn)
+        .await
+        .map_err(|e| {
+            println!("[DESCRIBE EXECUTION]: {}", e);
+            ApiError::backend("DescribeExecution", e)
+        })?;
+    let until = description.stop_date.unwrap_or_else(Timestamp::now);
+    description.duration_millis = Some(until.millis_since(&description.start_date));
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .json(description))
+}
+
+/// The definition of the state machine an execution ran. `/describe` is the
+/// original name of this route and is kept for existing clients.
+#[routes]
+#[get("/{region}/{arn}/definition")]
+#[get("/{region}/{arn}/describe")]
+async fn execution_definition(
+    req: HttpRequest,
+    client: web::Data<dyn StepFunctions>,
+) -> Result<HttpResponse, ApiError> {
+    let (region, arn) = validate::target(&req, ArnKind::Execution)?;
+
+    println!("[EXECUTION DEFINITION]: {} {}", region, arn);
+
+    let descriptor = client
+        .describe_state_machine_for_execution(&region, &arn)
+        .await
+        .map_err(|e| {
+            println!("[EXECUTION DEFINITION]: {}", e);
+            ApiError::backend("DescribeStateMachineForExecution", e)
+        })?;
+    let definition = asl::lenient::parse(&descriptor.definition).map_err(|e| {
+        ApiError::Internal(format!(
+            "State machine definition is not a JSON object. {}",
+            e
+        ))
+    })?;
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .json(definition))
+}
+
+/// The definition of a state machine, or of the one an execution ran, as
+/// nodes and labelled edges.
+#[get("/{region}/{arn}/graph")]
+async fn definition_graph(
+    req: HttpRequest,
+    client: web::Data<dyn StepFunctions>,
+) -> Result<HttpResponse, ApiError> {
+    let (region, arn) = validate::target(&req, ArnKind::StateMachine)
+        .or_else(|_| validate::target(&req, ArnKind::Execution))?;
+
+    println!("[DEFINITION GRAPH]: {} {}", region, arn);
+
+    let definition = if arn.contains(":stateMachine:") {
+        client
+            .describe_state_machine(&region, &arn)
+            .await
+            .map_err(|e| ApiError::backend("DescribeStateMachine", e))?
+            .definition
+            .unwrap_or_default()
+    } else {
+        client
+            .describe_state_machine_for_execution(&region, &arn)
+            .await
+            .map_err(|e| ApiError::backend("DescribeStateMachineForExecution", e))?
+            .definition
+    };
+    let definition = asl::lenient::parse(&definition).map_err(|e| {
+        ApiError::Internal(format!(
+            "State machine definition is not a JSON object. {}",
+            e
+        ))
+    })?;
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .json(DefinitionGraph::new(definition)))
+}
+
+/// The whole history of an execution, following pages.
+async fn full_history(
+    client: &dyn StepFunctions,
+    region: &str,
+    arn: &str,
+) -> Result<Vec<Event>, BackendError> {
+    let mut query = HistoryQuery {
+        max_results: Some(validate::MAX_PAGE_SIZE),
+        ..HistoryQuery::default()
+    };
+    let mut events = Vec::new();
+    loop {
+        let page = client.get_execution_history(region, arn, &query).await?;
+        events.extend(page.events);
+        match page.next_token {
+            Some(token) => query.next_token = Some(token),
+            None => return Ok(events),
         }
+    }
+}
+
+/// The graph of the definition an execution ran, and its whole history.
+async fn graph_and_history(
+    client: &dyn StepFunctions,
+    region: &str,
+    arn: &str,
+) -> Result<(Graph, Vec<Event>), ApiError> {
+    let descriptor = client
+        .describe_state_machine_for_execution(region, arn)
+        .await
+        .map_err(|e| ApiError::backend("DescribeStateMachineForExecution", e))?;
+    let events = full_history(client, region, arn)
+        .await
+        .map_err(|e| ApiError::backend("GetExecutionHistory", e))?;
+    let definition = asl::lenient::parse(&descriptor.definition).map_err(|e| {
+        ApiError::Internal(format!(
+            "State machine definition is not a JSON object. {}",
+            e
+        ))
+    })?;
+    Ok((DefinitionGraph::new(definition).graph, events))
+}
+
+/// Per-state visit status of an execution, for colouring its graph.
+#[get("/{region}/{arn}/trace")]
+async fn execution_trace(
+    req: HttpRequest,
+    client: web::Data<dyn StepFunctions>,
+) -> Result<HttpResponse, ApiError> {
+    let (region, arn) = validate::target(&req, ArnKind::Execution)?;
+
+    println!("[EXECUTION TRACE]: {} {}", region, arn);
+
+    let (graph, events) = graph_and_history(client.as_ref(), &region, &arn).await?;
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .json(trace::trace(&graph, &events)))
+}
+
+/// Each state visit of an execution with its input, output and failures.
+#[get("/{region}/{arn}/steps")]
+async fn execution_steps(
+    req: HttpRequest,
+    client: web::Data<dyn StepFunctions>,
+) -> Result<HttpResponse, ApiError> {
+    let (region, arn) = validate::target(&req, ArnKind::Execution)?;
+
+    println!("[EXECUTION STEPS]: {} {}", region, arn);
+
+    let (graph, events) = graph_and_history(client.as_ref(), &region, &arn).await?;
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .json(steps::steps(&graph, &events)))
+}
+
+/// An execution's events grouped by Map iteration and Parallel branch.
+#[get("/{region}/{arn}/groups")]
+async fn execution_groups(
+    req: HttpRequest,
+    client: web::Data<dyn StepFunctions>,
+) -> Result<HttpResponse, ApiError> {
+    let (region, arn) = validate::target(&req, ArnKind::Execution)?;
+
+    println!("[EXECUTION GROUPS]: {} {}", region, arn);
+
+    let (graph, events) = graph_and_history(client.as_ref(), &region, &arn).await?;
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .json(groups::group(&graph, &events)))
+}
+
+/// Every event of an execution with the events it caused.
+#[get("/{region}/{arn}/causes")]
+async fn execution_causes(
+    req: HttpRequest,
+    client: web::Data<dyn StepFunctions>,
+) -> Result<HttpResponse, ApiError> {
+    let (region, arn) = validate::target(&req, ArnKind::Execution)?;
+
+    println!("[EXECUTION CAUSES]: {} {}", region, arn);
+
+    let events = full_history(client.as_ref(), &region, &arn)
+        .await
+        .map_err(|e| {
+            println!("[EXECUTION CAUSES]: {}", e);
+            ApiError::backend("GetExecutionHistory", e)
+        })?;
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .json(causes::tree(&events)))
+}
+
+/// The events that led to one event, and the input of the state it happened in.
+#[get("/{region}/{arn}/causes/chain")]
+async fn execution_cause_chain(
+    req: HttpRequest,
+    client: web::Data<dyn StepFunctions>,
+) -> Result<HttpResponse, ApiError> {
+    let (region, arn) = validate::target(&req, ArnKind::Execution)?;
+    let query: CauseChainQuery = validate::query(&req)?;
+
+    println!(
+        "[EXECUTION CAUSE CHAIN]: {} {} {}",
+        region, arn, query.event_id
+    );
+
+    let events = full_history(client.as_ref(), &region, &arn)
+        .await
+        .map_err(|e| {
+            println!("[EXECUTION CAUSE CHAIN]: {}", e);
+            ApiError::backend("GetExecutionHistory", e)
+        })?;
+    let chain = causes::chain(&events, query.event_id).ok_or(ApiError::UnknownEvent {
+        arn: arn.clone(),
+        id: query.event_id,
+    })?;
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .json(chain))
+}
+
+fn routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_state_machines)
+        .service(get_state_machine)
+        .service(get_executions)
+        .service(execution)
+        .service(describe_execution)
+        .service(execution_definition)
+        .service(definition_graph)
+        .service(execution_trace)
+        .service(execution_steps)
+        .service(execution_groups)
+        .service(execution_causes)
+        .service(execution_cause_chain)
+        .service(start_execution)
+        .service(rerun_execution)
+        .service(stop_execution)
+        .service(create_state_machine)
+        .service(update_state_machine)
+        .service(delete_state_machine)
+        .service(stop_executions)
+        .service(delete_state_machines);
 }
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
-    println!("Starting server at port: {}", PORT);
+    let config = Config::load().map_err(std::io::Error::other)?;
+    println!(
+        "Starting server at {}:{} ({:?} backend, endpoint {})",
+        config.bind, config.port, config.backend, config.endpoint
+    );
+
+    let backend: Arc<dyn StepFunctions> = match config.backend {
+        BackendKind::Cli => Arc::new(CliBackend::new(config.endpoint.as_str())),
+        BackendKind::Http => Arc::new(HttpBackend::new(config.endpoint.as_str())),
+    };
+    let client: web::Data<dyn StepFunctions> = web::Data::from(Arc::new(Bounded::new(
+        backend,
+        config.max_concurrency,
+        config.timeout,
+    )) as Arc<dyn StepFunctions>);
+    let cors_origins = config.cors_origins.clone();
 
-    HttpServer::new(|| {
-        let cors = Cors::default()
-            .allowed_origin("http://localhost:8080")
+    HttpServer::new(move || {
+        let cors = cors_origins
+            .iter()
+            .fold(Cors::default(), |cors, origin| cors.allowed_origin(origin))
             .allowed_headers(vec![http::header::AUTHORIZATION, http::header::ACCEPT])
             .allowed_header(http::header::CONTENT_TYPE)
             .allow_any_method();
 
-
         App::new()
             .wrap(cors)
-            .service(get_state_machines)
-            .service(get_state_machine)
-            .service(get_executions)
-            .service(execution)
-            .service(describe_execution)
-            .service(stop_execution)
-            .service(delete_state_machine)
+            .app_data(client.clone())
+            .configure(routes)
     })
-    .bind(("127.0.0.1", PORT))?
+    .bind((config.bind.as_str(), config.port))?
     .run()
-    .await 
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::FakeBackend;
+    use crate::model::StateMachine;
+    use actix_web::dev::{ServiceFactory, ServiceRequest, ServiceResponse};
+    use actix_web::test;
+
+    const MACHINE_ARN: &str = "arn:aws:states:us-east-1:123456789012:stateMachine:orders";
+    const EXECUTION_ARN: &str = "arn:aws:states:us-east-1:123456789012:execution:orders:first";
+
+    fn orders_machine() -> StateMachine {
+        StateMachine {
+            name: String::from("orders"),
+            state_machine_arn: String::from(MACHINE_ARN),
+            kind: String::from("STANDARD"),
+            creation_date: Timestamp::parse("2023-11-10T10:36:00Z").unwrap(),
+            definition: None,
+        }
+    }
+
+    fn first_execution() -> Executions {
+        Executions {
+            execution_arn: String::from(EXECUTION_ARN),
+            state_machine_arn: String::from(MACHINE_ARN),
+            name: String::from("first"),
+            status: String::from("RUNNING"),
+            start_date: Timestamp::parse("2023-11-10T10:36:00Z").unwrap(),
+            stop_date: None,
+            duration_millis: None,
+        }
+    }
+
+    fn fake() -> web::Data<dyn StepFunctions> {
+        let backend = FakeBackend::new()
+            .with_state_machine(
+                orders_machine(),
+                r#"{"Comment":"orders","StartAt":"Done","States":{"Done":{"Type":"Succeed"}}}"#,
+            )
+            .with_execution(first_execution(), vec![]);
+        web::Data::from(Arc::new(backend) as Arc<dyn StepFunctions>)
+    }
+
+    /// The app over a backend running `definition`, whose only execution is
+    /// `listed`, with `history`.
+    fn app_with_execution(
+        definition: &str,
+        listed: Executions,
+        history: Vec<Event>,
+    ) -> App<
+        impl ServiceFactory<
+            ServiceRequest,
+            Config = (),
+            Response = ServiceResponse,
+            Error = actix_web::Error,
+            InitError = (),
+        >,
+    > {
+        let backend = FakeBackend::new()
+            .with_state_machine(orders_machine(), definition)
+            .with_execution(listed, history);
+        let backend = web::Data::from(Arc::new(backend) as Arc<dyn StepFunctions>);
+        App::new().app_data(backend).configure(routes)
+    }
+
+    /// The app over a backend whose only execution, `first_execution()`, has `history`.
+    fn app_with_history(
+        history: Vec<Event>,
+    ) -> App<
+        impl ServiceFactory<
+            ServiceRequest,
+            Config = (),
+            Response = ServiceResponse,
+            Error = actix_web::Error,
+            InitError = (),
+        >,
+    > {
+        app_with_execution("{}", first_execution(), history)
+    }
+
+    #[actix_web::test]
+    async fn lists_state_machines() {
+        let app = test::init_service(App::new().app_data(fake()).configure(routes)).await;
+        let req = test::TestRequest::get()
+            .uri("/us-east-1/state-machines")
+            .to_request();
+        let body: Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["stateMachines"][0]["name"], "orders");
+    }
+
+    #[actix_web::test]
+    async fn describes_the_definition_of_an_execution() {
+        let app = test::init_service(App::new().app_data(fake()).configure(routes)).await;
+        let req = test::TestRequest::get()
+            .uri(&format!("/us-east-1/{EXECUTION_ARN}/describe"))
+            .to_request();
+        let body: Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["StartAt"], "Done");
+        assert_eq!(body["warnings"], serde_json::json!([]));
+    }
+
+    #[actix_web::test]
+    async fn the_definition_route_matches_describe() {
+        let app = test::init_service(App::new().app_data(fake()).configure(routes)).await;
+        let req = test::TestRequest::get()
+            .uri(&format!("/us-east-1/{EXECUTION_ARN}/definition"))
+            .to_request();
+        let body: Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["Comment"], "orders");
+    }
+
+    #[actix_web::test]
+    async fn describes_an_execution() {
+        let stopped = Executions {
+            stop_date: Timestamp::parse("2023-11-10T10:36:02.5Z"),
+            status: String::from("SUCCEEDED"),
+            ..first_execution()
+        };
+        let history: Vec<Event> = serde_json::from_value(serde_json::json!([
+            {"timestamp": "2023-11-10T10:36:00Z", "type": "ExecutionStarted", "id": 1,
+             "executionStartedEventDetails": {"input": "{\"orderId\":7}", "roleArn": "arn:role"}}
+        ]))
+        .unwrap();
+        let app = test::init_service(app_with_execution("{}", stopped, history)).await;
+        let req = test::TestRequest::get()
+            .uri(&format!("/us-east-1/{EXECUTION_ARN}/execution"))
+            .to_request();
+        let body: Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["status"], "SUCCEEDED");
+        assert_eq!(body["input"], r#"{"orderId":7}"#);
+        assert_eq!(body["startDate"], "2023-11-10T10:36:00.000Z");
+        assert_eq!(body["stopDate"], "2023-11-10T10:36:02.500Z");
+        assert_eq!(body["durationMillis"], 2500);
+    }
+
+    #[actix_web::test]
+    async fn running_executions_count_up_to_now() {
+        let app = test::init_service(App::new().app_data(fake()).configure(routes)).await;
+        let req = test::TestRequest::get()
+            .uri(&format!("/us-east-1/{EXECUTION_ARN}/execution"))
+            .to_request();
+        let body: Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["status"], "RUNNING");
+        assert_eq!(body["stopDate"], Value::Null);
+        assert!(body["durationMillis"].as_i64().unwrap() > 0);
+    }
+
+    #[actix_web::test]
+    async fn describes_definitions_the_model_does_not_know() {
+        let backend = FakeBackend::new()
+            .with_state_machine(
+                orders_machine(),
+                r#"{"StartAt":"Jump","States":{"Jump":{"Type":"Teleport","End":true}}}"#,
+            )
+            .with_execution(first_execution(), vec![]);
+        let backend = web::Data::from(Arc::new(backend) as Arc<dyn StepFunctions>);
+        let app = test::init_service(App::new().app_data(backend).configure(routes)).await;
+        let req = test::TestRequest::get()
+            .uri(&format!("/us-east-1/{EXECUTION_ARN}/describe"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body: Value = test::read_body_json(resp).await;
+        assert_eq!(body["States"]["Jump"]["Type"], "Teleport");
+        assert_eq!(body["warnings"][0]["path"], "/States/Jump");
+    }
+
+    #[actix_web::test]
+    async fn lists_executions_with_durations() {
+        let stopped = Executions {
+            execution_arn: format!("{MACHINE_ARN}:second").replace(":stateMachine:", ":execution:"),
+            name: String::from("second"),
+            status: String::from("SUCCEEDED"),
+            stop_date: Timestamp::parse("2023-11-10T10:36:01.250Z"),
+            ..first_execution()
+        };
+        let backend = FakeBackend::new()
+            .with_state_machine(orders_machine(), "{}")
+            .with_execution(first_execution(), vec![])
+            .with_execution(stopped, vec![]);
+        let backend = web::Data::from(Arc::new(backend) as Arc<dyn StepFunctions>);
+        let app = test::init_service(App::new().app_data(backend).configure(routes)).await;
+        let req = test::TestRequest::get()
+            .uri(&format!("/us-east-1/{MACHINE_ARN}/executions"))
+            .to_request();
+        let body: Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(
+            body["executions"][0]["startDate"],
+            "2023-11-10T10:36:00.000Z"
+        );
+        assert!(body["executions"][0].get("durationMillis").is_none());
+        assert_eq!(body["executions"][1]["durationMillis"], 1250);
+    }
+
+    #[actix_web::test]
+    async fn pages_and_filters_executions() {
+        let mut backend = FakeBackend::new().with_state_machine(orders_machine(), "{}");
+        for (index, status) in ["SUCCEEDED", "FAILED", "SUCCEEDED", "SUCCEEDED"]
+            .into_iter()
+            .enumerate()
+        {
+            backend = backend.with_execution(
+                Executions {
+                    execution_arn: format!("{EXECUTION_ARN}-{index}"),
+                    name: format!("run-{index}"),
+                    status: String::from(status),
+                    ..first_execution()
+                },
+                vec![],
+            );
+        }
+        let backend = web::Data::from(Arc::new(backend) as Arc<dyn StepFunctions>);
+        let app = test::init_service(App::new().app_data(backend).configure(routes)).await;
+
+        let req = test::TestRequest::get()
+            .uri(&format!(
+                "/us-east-1/{MACHINE_ARN}/executions?status=SUCCEEDED&limit=2"
+            ))
+            .to_request();
+        let body: Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["executions"][0]["name"], "run-0");
+        assert_eq!(body["executions"][1]["name"], "run-2");
+        let token = body["nextToken"].as_str().unwrap();
+
+        let req = test::TestRequest::get()
+            .uri(&format!(
+                "/us-east-1/{MACHINE_ARN}/executions?status=SUCCEEDED&limit=2&nextToken={token}"
+            ))
+            .to_request();
+        let body: Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["executions"].as_array().unwrap().len(), 1);
+        assert_eq!(body["executions"][0]["name"], "run-3");
+        assert!(body.get("nextToken").is_none());
+
+        let req = test::TestRequest::get()
+            .uri(&format!("/us-east-1/{MACHINE_ARN}/executions?status=DONE"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        let body: Value = test::read_body_json(resp).await;
+        assert_eq!(body["code"], "InvalidQuery");
+    }
+
+    #[actix_web::test]
+    async fn pages_history_newest_first() {
+        let history: Vec<Event> = (1..=5)
+            .map(|id| {
+                serde_json::from_value(serde_json::json!({
+                    "timestamp": "2023-11-10T10:36:00Z",
+                    "type": "ParallelStateStarted",
+                    "id": id,
+                }))
+                .unwrap()
+            })
+            .collect();
+        let app = test::init_service(app_with_history(history)).await;
+
+        let ids = |body: &Value| -> Vec<u64> {
+            body["events"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|event| event["id"].as_u64().unwrap())
+                .collect()
+        };
+        let req = test::TestRequest::get()
+            .uri(&format!(
+                "/us-east-1/{EXECUTION_ARN}/history?reverseOrder=true&maxResults=3"
+            ))
+            .to_request();
+        let body: Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(ids(&body), [5, 4, 3]);
+        let token = body["nextToken"].as_str().unwrap();
+
+        let req = test::TestRequest::get()
+            .uri(&format!(
+                "/us-east-1/{EXECUTION_ARN}/history?reverseOrder=true&maxResults=3&nextToken={token}"
+            ))
+            .to_request();
+        let body: Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(ids(&body), [2, 1]);
+        assert!(body.get("nextToken").is_none());
+
+        let req = test::TestRequest::get()
+            .uri(&format!("/us-east-1/{EXECUTION_ARN}/history?maxResults=0"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[actix_web::test]
+    async fn history_includes_state_durations() {
+        let history: Vec<Event> = serde_json::from_value(serde_json::json!([
+            {"timestamp": "2023-11-10T10:36:00.000Z", "type": "PassStateEntered", "id": 2,
+             "previousEventId": 1, "stateEnteredEventDetails": {"name": "Done", "input": "{}"}},
+            {"timestamp": "2023-11-10T10:36:00.040Z", "type": "PassStateExited", "id": 3,
+             "previousEventId": 2, "stateExitedEventDetails": {"name": "Done", "output": "{}"}}
+        ]))
+        .unwrap();
+        let app = test::init_service(app_with_history(history)).await;
+        let req = test::TestRequest::get()
+            .uri(&format!("/us-east-1/{EXECUTION_ARN}/history"))
+            .to_request();
+        let body: Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["events"][1]["timestamp"], "2023-11-10T10:36:00.040Z");
+        assert_eq!(body["stateDurations"][0]["name"], "Done");
+        assert_eq!(body["stateDurations"][0]["durationMillis"], 40);
+    }
+
+    #[actix_web::test]
+    async fn steps_pair_inputs_with_outputs() {
+        let history: Vec<Event> = serde_json::from_value(serde_json::json!([
+            {"timestamp": "2023-11-10T10:36:00.000Z", "type": "ParallelStateEntered", "id": 2,
+             "previousEventId": 1, "stateEnteredEventDetails": {"name": "Notify", "input": "{}"}},
+            {"timestamp": "2023-11-10T10:36:00.010Z", "type": "ParallelStateStarted", "id": 3,
+             "previousEventId": 2},
+            {"timestamp": "2023-11-10T10:36:00.020Z", "type": "TaskStateEntered", "id": 4,
+             "previousEventId": 3, "stateEnteredEventDetails": {"name": "SendEmail", "input": "{\"a\":1}"}},
+            {"timestamp": "2023-11-10T10:36:00.030Z", "type": "TaskStateEntered", "id": 5,
+             "previousEventId": 3, "stateEnteredEventDetails": {"name": "SendSms", "input": "{}"}},
+            {"timestamp": "2023-11-10T10:36:00.050Z", "type": "TaskStateExited", "id": 6,
+             "previousEventId": 5, "stateExitedEventDetails": {"name": "SendSms", "output": "{}"}},
+            {"timestamp": "2023-11-10T10:36:00.060Z", "type": "TaskStateExited", "id": 7,
+             "previousEventId": 4, "stateExitedEventDetails": {"name": "SendEmail", "output": "{\"b\":2}"}}
+        ]))
+        .unwrap();
+        let definition = include_str!("../fixtures/asl/parallel.asl.json");
+        let app =
+            test::init_service(app_with_execution(definition, first_execution(), history)).await;
+        let req = test::TestRequest::get()
+            .uri(&format!("/us-east-1/{EXECUTION_ARN}/steps"))
+            .to_request();
+        let body: Value = test::call_and_read_body_json(&app, req).await;
+        let email = &body[1];
+        assert_eq!(email["name"], "SendEmail");
+        assert_eq!(email["input"], "{\"a\":1}");
+        assert_eq!(email["output"], "{\"b\":2}");
+        assert_eq!(email["durationMillis"], 40);
+        assert_eq!(email["retries"], 0);
+        assert_eq!(email["parent"]["state"], "Notify");
+        assert_eq!(email["parent"]["branch"], 0);
+        assert_eq!(body[2]["parent"]["branch"], 1);
+    }
+
+    #[actix_web::test]
+    async fn groups_split_map_iterations() {
+        let history: Vec<Event> = serde_json::from_value(serde_json::json!([
+            {"timestamp": "2023-11-10T10:36:00.000Z", "type": "MapStateEntered", "id": 2,
+             "previousEventId": 1, "stateEnteredEventDetails": {"name": "Each", "input": "[1]"}},
+            {"timestamp": "2023-11-10T10:36:00.010Z", "type": "MapIterationStarted", "id": 3,
+             "previousEventId": 2, "mapIterationStartedEventDetails": {"name": "Each", "index": 0}},
+            {"timestamp": "2023-11-10T10:36:00.030Z", "type": "MapIterationSucceeded", "id": 4,
+             "previousEventId": 3, "mapIterationSucceededEventDetails": {"name": "Each", "index": 0}}
+        ]))
+        .unwrap();
+        let app = test::init_service(app_with_history(history)).await;
+        let req = test::TestRequest::get()
+            .uri(&format!("/us-east-1/{EXECUTION_ARN}/groups"))
+            .to_request();
+        let body: Value = test::call_and_read_body_json(&app, req).await;
+        let iteration = &body["groups"][0]["lanes"][0];
+        assert_eq!(body["groups"][0]["type"], "Map");
+        assert_eq!(iteration["iteration"], 0);
+        assert_eq!(iteration["status"], "SUCCEEDED");
+        assert_eq!(iteration["durationMillis"], 20);
+    }
+
+    #[actix_web::test]
+    async fn cause_chains_need_a_known_event() {
+        let history: Vec<Event> = serde_json::from_value(serde_json::json!([
+            {"timestamp": "2023-11-10T10:36:00.000Z", "type": "ExecutionStarted", "id": 1,
+             "executionStartedEventDetails": {"input": "{}", "roleArn": "arn:role"}}
+        ]))
+        .unwrap();
+        let app = test::init_service(app_with_history(history)).await;
+        let req = test::TestRequest::get()
+            .uri(&format!(
+                "/us-east-1/{EXECUTION_ARN}/causes/chain?eventId=1"
+            ))
+            .to_request();
+        let body: Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["events"][0]["id"], 1);
+
+        let req = test::TestRequest::get()
+            .uri(&format!(
+                "/us-east-1/{EXECUTION_ARN}/causes/chain?eventId=99"
+            ))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+        let body: Value = test::read_body_json(resp).await;
+        assert_eq!(body["code"], "EventDoesNotExist");
+    }
+
+    #[actix_web::test]
+    async fn stops_an_execution() {
+        let backend = fake();
+        let app = test::init_service(App::new().app_data(backend.clone()).configure(routes)).await;
+        let req = test::TestRequest::post()
+            .uri(&format!("/us-east-1/{EXECUTION_ARN}/stop-execution"))
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), StatusCode::OK);
+
+        let executions = backend
+            .list_executions("us-east-1", MACHINE_ARN, &ExecutionsQuery::default())
+            .await
+            .unwrap();
+        assert_eq!(executions.executions[0].status, "ABORTED");
+    }
+
+    #[actix_web::test]
+    async fn stops_an_execution_with_a_chosen_reason() {
+        let backend = fake();
+        let app = test::init_service(App::new().app_data(backend.clone()).configure(routes)).await;
+        let req = test::TestRequest::post()
+            .uri(&format!("/us-east-1/{EXECUTION_ARN}/stop-execution"))
+            .set_payload(r#"{"error":"Payment.Declined","cause":"card expired"}"#)
+            .to_request();
+        let body: Value = test::call_and_read_body_json(&app, req).await;
+        assert!(Timestamp::parse(body["stopDate"].as_str().unwrap()).is_some());
+
+        let description = backend
+            .describe_execution("us-east-1", EXECUTION_ARN)
+            .await
+            .unwrap();
+        assert_eq!(description.error.as_deref(), Some("Payment.Declined"));
+        assert_eq!(description.cause.as_deref(), Some("card expired"));
+
+        let req = test::TestRequest::post()
+            .uri(&format!("/us-east-1/{EXECUTION_ARN}/stop-execution"))
+            .set_payload(format!(r#"{{"error":"{}"}}"#, "E".repeat(257)))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[actix_web::test]
+    async fn stops_with_the_default_reason_when_the_ui_posts_null() {
+        let backend = fake();
+        let app = test::init_service(App::new().app_data(backend.clone()).configure(routes)).await;
+        let req = test::TestRequest::post()
+            .uri(&format!("/us-east-1/{EXECUTION_ARN}/stop-execution"))
+            .set_payload("null")
+            .to_request();
+        let body: Value = test::call_and_read_body_json(&app, req).await;
+        assert!(body["stopDate"].is_string());
+
+        let description = backend
+            .describe_execution("us-east-1", EXECUTION_ARN)
+            .await
+            .unwrap();
+        assert_eq!(description.error.as_deref(), Some("manualstop"));
+    }
+
+    #[actix_web::test]
+    async fn running_executions_block_deletes_unless_forced() {
+        let backend = fake();
+        let app = test::init_service(App::new().app_data(backend.clone()).configure(routes)).await;
+        let uri = format!("/us-east-1/{MACHINE_ARN}/state-machine");
+
+        let req = test::TestRequest::delete()
+            .uri(&format!("{uri}?dryRun=true"))
+            .to_request();
+        let body: Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["runningExecutions"], 1);
+        assert_eq!(body["deleted"], false);
+
+        let req = test::TestRequest::delete().uri(&uri).to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::CONFLICT);
+        let body: Value = test::read_body_json(resp).await;
+        assert_eq!(body["code"], "RunningExecutions");
+        let machines = backend.list_state_machines("us-east-1").await.unwrap();
+        assert_eq!(machines.state_machines.len(), 1);
+
+        let req = test::TestRequest::delete()
+            .uri(&format!("{uri}?force=true"))
+            .to_request();
+        let body: Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["deleted"], true);
+        let machines = backend.list_state_machines("us-east-1").await.unwrap();
+        assert!(machines.state_machines.is_empty());
+
+        let req = test::TestRequest::delete().uri(&uri).to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[actix_web::test]
+    async fn stops_running_executions_by_name_prefix() {
+        let backend = fake();
+        for name in ["load-1", "load-2"] {
+            backend
+                .start_execution("us-east-1", MACHINE_ARN, Some(name), "{}")
+                .await
+                .unwrap();
+        }
+        let app = test::init_service(App::new().app_data(backend.clone()).configure(routes)).await;
+        let req = test::TestRequest::post()
+            .uri(&format!("/us-east-1/{MACHINE_ARN}/stop-executions"))
+            .set_payload(r#"{"namePrefix":"load-","error":"Test.Cleanup"}"#)
+            .to_request();
+        let body: Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["succeeded"], 2);
+        assert_eq!(body["failed"], 0);
+        assert!(body["results"][0]["arn"]
+            .as_str()
+            .unwrap()
+            .ends_with(":load-1"));
+        assert!(body["results"][0]["result"]["stopDate"].is_string());
+
+        let statuses: Vec<String> = backend
+            .list_executions("us-east-1", MACHINE_ARN, &ExecutionsQuery::default())
+            .await
+            .unwrap()
+            .executions
+            .into_iter()
+            .map(|listed| listed.status)
+            .collect();
+        assert_eq!(statuses, ["RUNNING", "ABORTED", "ABORTED"]);
+    }
+
+    #[actix_web::test]
+    async fn bulk_stops_accept_a_null_body() {
+        let app = test::init_service(App::new().app_data(fake()).configure(routes)).await;
+        let req = test::TestRequest::post()
+            .uri(&format!("/us-east-1/{MACHINE_ARN}/stop-executions"))
+            .set_payload("null")
+            .to_request();
+        let body: Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["succeeded"], 1);
+        assert_eq!(body["failed"], 0);
+    }
+
+    #[actix_web::test]
+    async fn bulk_deletes_report_each_machine() {
+        let backend = fake();
+        let app = test::init_service(App::new().app_data(backend.clone()).configure(routes)).await;
+        let missing = MACHINE_ARN.replace(":orders", ":gone");
+        let req = test::TestRequest::post()
+            .uri("/us-east-1/state-machines/delete")
+            .set_payload(
+                serde_json::json!({ "stateMachineArns": [MACHINE_ARN, "not-an-arn", missing] })
+                    .to_string(),
+            )
+            .to_request();
+        let body: Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["succeeded"], 0);
+        let results = body["results"].as_array().unwrap();
+        assert_eq!(results[0]["status"], 409);
+        assert_eq!(results[0]["error"]["code"], "RunningExecutions");
+        assert_eq!(results[1]["status"], 400);
+        assert_eq!(results[1]["error"]["code"], "InvalidArn");
+        assert_eq!(results[2]["status"], 404);
+
+        let req = test::TestRequest::post()
+            .uri("/us-east-1/state-machines/delete")
+            .set_payload(
+                serde_json::json!({ "stateMachineArns": [MACHINE_ARN], "force": true }).to_string(),
+            )
+            .to_request();
+        let body: Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["succeeded"], 1);
+        assert_eq!(body["results"][0]["result"]["deleted"], true);
+        let machines = backend.list_state_machines("us-east-1").await.unwrap();
+        assert!(machines.state_machines.is_empty());
+    }
+
+    #[actix_web::test]
+    async fn graphs_state_machines_and_executions_alike() {
+        let app = test::init_service(App::new().app_data(fake()).configure(routes)).await;
+        let mut graphs = Vec::new();
+        for arn in [MACHINE_ARN, EXECUTION_ARN] {
+            let req = test::TestRequest::get()
+                .uri(&format!("/us-east-1/{arn}/graph"))
+                .to_request();
+            let body: Value = test::call_and_read_body_json(&app, req).await;
+            graphs.push(body);
+        }
+        assert_eq!(graphs[0], graphs[1]);
+        assert_eq!(graphs[0]["nodes"][0]["id"], "/States/Done");
+        assert_eq!(graphs[0]["nodes"][0]["type"], "Succeed");
+        assert_eq!(graphs[0]["nodes"][0]["start"], true);
+    }
+
+    #[actix_web::test]
+    async fn traces_cover_every_state_of_the_definition() {
+        let app = test::init_service(App::new().app_data(fake()).configure(routes)).await;
+        let req = test::TestRequest::get()
+            .uri(&format!("/us-east-1/{EXECUTION_ARN}/trace"))
+            .to_request();
+        let body: Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(
+            body["states"],
+            serde_json::json!([{
+                "id": "/States/Done",
+                "name": "Done",
+                "status": "NOT_VISITED",
+                "visitCount": 0
+            }])
+        );
+    }
+
+    #[actix_web::test]
+    async fn unknown_execution_is_an_error() {
+        let app = test::init_service(App::new().app_data(fake()).configure(routes)).await;
+        let req = test::TestRequest::get()
+            .uri(
+                "/us-east-1/arn:aws:states:us-east-1:123456789012:execution:orders:missing/history",
+            )
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[actix_web::test]
+    async fn malicious_path_segments_are_rejected() {
+        let app = test::init_service(App::new().app_data(fake()).configure(routes)).await;
+
+        for (uri, code) in [
+            ("/us-east-1%3Brm%20-rf%20~/state-machines", "InvalidRegion"),
+            (
+                "/%24(touch%20pwned)/arn:aws:states:us-east-1:123456789012:stateMachine:orders/executions",
+                "InvalidRegion",
+            ),
+            (
+                "/us-east-1/arn:aws:states:us-east-1:123456789012:execution:orders:x%3B%20id/history",
+                "InvalidArn",
+            ),
+            (
+                "/us-east-1/arn:aws:states:us-east-1:123456789012:stateMachine:orders%20--endpoint-url%20http:%2F%2Fevil/state-machine",
+                "InvalidArn",
+            ),
+        ] {
+            let req = test::TestRequest::get().uri(uri).to_request();
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(resp.status(), StatusCode::BAD_REQUEST, "{uri}");
+            let body: Value = test::read_body_json(resp).await;
+            assert_eq!(body["code"], code, "{uri}");
+        }
+    }
+
+    #[actix_web::test]
+    async fn backend_timeouts_are_gateway_timeouts() {
+        let resp = ApiError::backend(
+            "ListStateMachines",
+            BackendError::Timeout(std::time::Duration::from_secs(30)),
+        )
+        .error_response();
+        assert_eq!(resp.status(), StatusCode::GATEWAY_TIMEOUT);
+
+        let resp = ApiError::backend(
+            "ListStateMachines",
+            BackendError::Unavailable(String::from("connection refused")),
+        )
+        .error_response();
+        assert_eq!(resp.status(), StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    #[actix_web::test]
+    async fn missing_resources_are_not_found() {
+        let app = test::init_service(App::new().app_data(fake()).configure(routes)).await;
+        for uri in [
+            format!("/us-east-1/{MACHINE_ARN}x/state-machine"),
+            format!("/us-east-1/{EXECUTION_ARN}x/execution"),
+            format!("/us-east-1/{EXECUTION_ARN}x/history"),
+        ] {
+            let req = test::TestRequest::get().uri(&uri).to_request();
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(resp.status(), StatusCode::NOT_FOUND, "{uri}");
+            let body: Value = test::read_body_json(resp).await;
+            assert!(body["code"].as_str().unwrap().ends_with("DoesNotExist"));
+        }
+    }
+
+    #[actix_web::test]
+    async fn starts_an_execution_with_json_input() {
+        let backend = fake();
+        let app = test::init_service(App::new().app_data(backend.clone()).configure(routes)).await;
+        let req = test::TestRequest::post()
+            .uri(&format!("/us-east-1/{MACHINE_ARN}/start-execution"))
+            .set_payload(r#"{"name":"from-ui","input":{"orderId":7}}"#)
+            .to_request();
+        let body: Value = test::call_and_read_body_json(&app, req).await;
+        let execution_arn = body["executionArn"].as_str().unwrap();
+        assert_eq!(
+            execution_arn,
+            "arn:aws:states:us-east-1:123456789012:execution:orders:from-ui"
+        );
+        assert!(body["startDate"].is_string());
+
+        let history = backend
+            .get_execution_history("us-east-1", execution_arn, &HistoryQuery::default())
+            .await
+            .unwrap();
+        let started = history.events[0].execution_started().unwrap();
+        assert_eq!(started.input, r#"{"orderId":7}"#);
+    }
+
+    #[actix_web::test]
+    async fn start_execution_rejects_input_that_is_not_json() {
+        let app = test::init_service(App::new().app_data(fake()).configure(routes)).await;
+        for (payload, code) in [
+            (r#"{"input":"{orderId: 7"}"#, "InvalidExecutionInput"),
+            (r#"{"input": }"#, "InvalidRequestBody"),
+            (r#"{"name":"bad name"}"#, "InvalidName"),
+        ] {
+            let req = test::TestRequest::post()
+                .uri(&format!("/us-east-1/{MACHINE_ARN}/start-execution"))
+                .set_payload(payload)
+                .to_request();
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(resp.status(), StatusCode::BAD_REQUEST, "{payload}");
+            let body: Value = test::read_body_json(resp).await;
+            assert_eq!(body["code"], code, "{payload}");
+        }
+    }
+
+    #[actix_web::test]
+    async fn reruns_an_execution_with_patched_input() {
+        let backend = fake();
+        let started = backend
+            .start_execution(
+                "us-east-1",
+                MACHINE_ARN,
+                Some("original"),
+                r#"{"orderId":7,"retry":false,"debug":true}"#,
+            )
+            .await
+            .unwrap();
+        let app = test::init_service(App::new().app_data(backend.clone()).configure(routes)).await;
+
+        let req = test::TestRequest::post()
+            .uri(&format!("/us-east-1/{}/rerun", started.execution_arn))
+            .set_payload(r#"{"retry":true,"debug":null}"#)
+            .to_request();
+        let body: Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["originalExecutionArn"], started.execution_arn.as_str());
+        assert_eq!(
+            body["originalHistory"],
+            format!("/us-east-1/{}/history", started.execution_arn)
+        );
+
+        let history = backend
+            .get_execution_history(
+                "us-east-1",
+                body["executionArn"].as_str().unwrap(),
+                &HistoryQuery::default(),
+            )
+            .await
+            .unwrap();
+        let input = &history.events[0].execution_started().unwrap().input;
+        assert_eq!(
+            serde_json::from_str::<Value>(input).unwrap(),
+            serde_json::json!({"orderId": 7, "retry": true})
+        );
+    }
+
+    #[actix_web::test]
+    async fn reruns_with_identical_input_without_a_body() {
+        let backend = fake();
+        let started = backend
+            .start_execution("us-east-1", MACHINE_ARN, None, r#"{"orderId":7}"#)
+            .await
+            .unwrap();
+        let app = test::init_service(App::new().app_data(backend.clone()).configure(routes)).await;
+
+        let req = test::TestRequest::post()
+            .uri(&format!("/us-east-1/{}/rerun", started.execution_arn))
+            .to_request();
+        let body: Value = test::call_and_read_body_json(&app, req).await;
+        let history = backend
+            .get_execution_history(
+                "us-east-1",
+                body["executionArn"].as_str().unwrap(),
+                &HistoryQuery::default(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            history.events[0].execution_started().unwrap().input,
+            r#"{"orderId":7}"#
+        );
+    }
+
+    #[actix_web::test]
+    async fn a_null_rerun_body_replaces_the_input() {
+        let backend = fake();
+        let started = backend
+            .start_execution("us-east-1", MACHINE_ARN, None, r#"{"orderId":7}"#)
+            .await
+            .unwrap();
+        let app = test::init_service(App::new().app_data(backend.clone()).configure(routes)).await;
+
+        let req = test::TestRequest::post()
+            .uri(&format!("/us-east-1/{}/rerun", started.execution_arn))
+            .set_payload("null")
+            .to_request();
+        let body: Value = test::call_and_read_body_json(&app, req).await;
+        let history = backend
+            .get_execution_history(
+                "us-east-1",
+                body["executionArn"].as_str().unwrap(),
+                &HistoryQuery::default(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(history.events[0].execution_started().unwrap().input, "null");
+    }
+
+    #[actix_web::test]
+    async fn creates_a_state_machine_from_a_valid_definition() {
+        let backend = fake();
+        let app = test::init_service(App::new().app_data(backend.clone()).configure(routes)).await;
+        let req = test::TestRequest::post()
+            .uri("/us-east-1/state-machines")
+            .set_payload(
+                r#"{"name":"refunds","definition":{"StartAt":"Done","Comment":"","States":{"Done":{"Type":"Succeed"}}}}"#,
+            )
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::CREATED);
+        let body: Value = test::read_body_json(resp).await;
+        let arn = body["stateMachineArn"].as_str().unwrap();
+
+        let machine = backend
+            .describe_state_machine("us-east-1", arn)
+            .await
+            .unwrap();
+        assert_eq!(machine.name, "refunds");
+        assert_eq!(machine.kind, "STANDARD");
+    }
+
+    #[actix_web::test]
+    async fn rejects_definitions_that_do_not_parse() {
+        let app = test::init_service(App::new().app_data(fake()).configure(routes)).await;
+        let req = test::TestRequest::post()
+            .uri("/us-east-1/state-machines")
+            .set_payload(r#"{"name":"refunds","definition":{"StartAt":"Done"}}"#)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        let body: Value = test::read_body_json(resp).await;
+        assert_eq!(body["code"], "InvalidDefinition");
+    }
+
+    #[actix_web::test]
+    async fn update_returns_a_diff_of_the_definition() {
+        let app = test::init_service(App::new().app_data(fake()).configure(routes)).await;
+        let req = test::TestRequest::put()
+            .uri(&format!("/us-east-1/{MACHINE_ARN}/state-machine"))
+            .set_payload(
+                r#"{"definition":{"Comment":"orders v2","StartAt":"Done","States":{"Done":{"Type":"Succeed"}}}}"#,
+            )
+            .to_request();
+        let body: Value = test::call_and_read_body_json(&app, req).await;
+        assert!(body["updateDate"].is_string());
+        assert_eq!(
+            body["diff"],
+            serde_json::json!([{
+                "path": "/Comment",
+                "op": "changed",
+                "old": "orders",
+                "new": "orders v2"
+            }])
+        );
+    }
 }
@@ -1,53 +1,7 @@
-use std::collections::BTreeMap;
-
-use chrono::DateTime;
-use serde::{de, Deserialize, Deserializer, Serialize};
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
-pub fn float_to_date_string<'de, D: Deserializer<'de>>(
-    deserializer: D,
-) -> Result<String, D::Error> {
-    Ok(match Value::deserialize(deserializer)? {
-        Value::Number(num) => {
-            let num = num
-                .as_f64()
-                .ok_or_else(|| de::Error::custom("Invalid number"))?;
-            format!(
-                "{}",
-                DateTime::from_timestamp(num as i64, 0)
-                    .ok_or_else(|| de::Error::custom("Invalid number"))?
-                    .format("%Y-%m-%d %H:%M")
-            )
-        }
-        e => return Err(de::Error::custom(e)),
-    })
-}
-
-#[derive(Deserialize, Serialize, Debug)]
-struct S {
-    #[serde(deserialize_with = "float_to_date_string")]
-    time: String,
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-    #[test]
-    fn int_float_to_date_string_works() {
-        let as_string = r#"{"time":1699612567.763}"#;
-
-        let my_s: S = serde_json::from_str(&as_string).unwrap();
-        assert_eq!(
-            my_s.time,
-            format!(
-                "{}",
-                DateTime::from_timestamp(1699612567, 0)
-                    .unwrap()
-                    .format("%Y-%m-%d %H:%M")
-            )
-        );
-    }
-}
+use crate::timestamp::Timestamp;
 
 #[derive(Deserialize, Serialize)]
 pub struct StateMachineResponse {
@@ -55,19 +9,58 @@ pub struct StateMachineResponse {
     pub state_machines: Vec<StateMachine>,
 }
 
-#[derive(Deserialize, Serialize)]
+#[derive(Clone, Deserialize, Serialize)]
 pub struct StateMachine {
     pub name: String,
     #[serde(rename = "stateMachineArn")]
     pub state_machine_arn: String,
     #[serde(rename = "type")]
     pub kind: String,
-    #[serde(deserialize_with = "float_to_date_string")]
     #[serde(rename = "creationDate")]
-    pub creation_date: String,
+    pub creation_date: Timestamp,
+    /// Only returned when describing a single state machine.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub definition: Option<String>,
 }
 
-#[derive(Deserialize, Serialize)]
+#[derive(Debug, Default, Deserialize)]
+pub struct CreateStateMachineRequest {
+    pub name: String,
+    pub definition: Value,
+    #[serde(rename = "roleArn")]
+    pub role_arn: Option<String>,
+    #[serde(rename = "type")]
+    pub kind: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct CreateStateMachineResponse {
+    #[serde(rename = "stateMachineArn")]
+    pub state_machine_arn: String,
+    #[serde(rename = "creationDate")]
+    pub creation_date: Timestamp,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct UpdateStateMachineRequest {
+    pub definition: Value,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct UpdateStateMachineResponse {
+    #[serde(rename = "updateDate")]
+    pub update_date: Timestamp,
+}
+
+#[derive(Debug, Serialize)]
+pub struct StateMachineUpdate {
+    #[serde(flatten)]
+    pub updated: UpdateStateMachineResponse,
+    /// Differences between the previous and the submitted definition.
+    pub diff: Vec<crate::diff::Change>,
+}
+
+#[derive(Clone, Deserialize, Serialize)]
 pub struct Executions {
     #[serde(rename = "executionArn")]
     pub execution_arn: String,
@@ -76,156 +69,187 @@ pub struct Executions {
     pub name: String,
     pub status: String,
     #[serde(rename = "startDate")]
-    pub start_date: String,
+    pub start_date: Timestamp,
+    #[serde(default)]
     #[serde(rename = "stopDate")]
-    pub stop_date: Option<String>,
+    pub stop_date: Option<Timestamp>,
+    /// Filled in by this server for executions that have stopped.
+    #[serde(
+        rename = "durationMillis",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub duration_millis: Option<i64>,
 }
 
-#[derive(Deserialize, Serialize)]
-pub struct ExecutionsResponse {
-    pub executions: Vec<Executions>,
+impl Executions {
+    /// Milliseconds from start to stop, or `None` while still running.
+    pub fn duration_millis(&self) -> Option<i64> {
+        self.stop_date
+            .as_ref()
+            .map(|stop| stop.millis_since(&self.start_date))
+    }
 }
 
-#[derive(Debug, Deserialize, Serialize)]
-pub struct Event {
-    pub timestamp: String,
-    #[serde(rename = "type")]
-    pub kind: String,
-    pub id: u64,
-    #[serde(rename = "previousEventId")]
-    pub previous_event_id: Option<u16>,
-    #[serde(rename = "executionStartedEventDetails")]
-    pub execution_started_event_details: Option<ExecutionStartedEventDetails>,
-    #[serde(rename = "stateEnteredEventDetails")]
-    pub state_entered_event_details: Option<StateEnteredEventDetails>,
-    #[serde(rename = "stateExitedEventDetails")]
-    pub state_exited_event_details: Option<StateExitedEventDetails>,
-    #[serde(rename = "lambdaFunctionScheduledEventDetails")]
-    pub lambda_function_scheduled_event_details: Option<LambdaFunctionScheduledEventDetails>,
-    #[serde(rename = "lambdaFunctionFailedEventDetails")]
-    pub lambda_function_failed_event_details: Option<LambdaFunctionFailedEventDetails>,
-    #[serde(rename = "executionSucceededEventDetails")]
-    pub execution_succeeded_event_details: Option<ExecutionSucceededEventDetails>,
-}
-
-#[derive(Debug, Deserialize, Serialize)]
-pub struct ExecutionStartedEventDetails {
-    pub input: String,
-    #[serde(rename = "roleArn")]
-    pub role_arn: String,
+/// One execution with its payloads, as returned by DescribeExecution.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ExecutionDescription {
+    #[serde(rename = "executionArn")]
+    pub execution_arn: String,
+    #[serde(rename = "stateMachineArn")]
+    pub state_machine_arn: String,
+    #[serde(default)]
+    pub name: String,
+    pub status: String,
+    #[serde(rename = "startDate")]
+    pub start_date: Timestamp,
+    #[serde(rename = "stopDate", default)]
+    pub stop_date: Option<Timestamp>,
+    #[serde(default)]
+    pub input: Option<String>,
+    #[serde(default)]
+    pub output: Option<String>,
+    #[serde(default)]
+    pub error: Option<String>,
+    #[serde(default)]
+    pub cause: Option<String>,
+    /// Filled in by this server; running executions count up to now.
+    #[serde(rename = "durationMillis", default)]
+    pub duration_millis: Option<i64>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
-pub struct StateEnteredEventDetails {
-    pub name: String,
-    pub input: String,
+#[derive(Debug, Default, Deserialize)]
+pub struct StartExecutionRequest {
+    pub name: Option<String>,
+    pub input: Option<Value>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
-pub struct StateExitedEventDetails {
-    pub name: String,
-    pub output: String,
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct StartExecutionResponse {
+    #[serde(rename = "executionArn")]
+    pub execution_arn: String,
+    #[serde(rename = "startDate")]
+    pub start_date: Timestamp,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
-pub struct LambdaFunctionScheduledEventDetails {
-    pub resource: String,
-    pub input: String,
+/// Optional body of `POST /{region}/{arn}/stop-execution`.
+#[derive(Debug, Default, Deserialize)]
+pub struct StopExecutionRequest {
+    pub error: Option<String>,
+    pub cause: Option<String>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
-pub struct LambdaFunctionFailedEventDetails {
-    pub error: String,
-    pub cause: String,
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct StopExecutionResponse {
+    #[serde(rename = "stopDate")]
+    pub stop_date: Timestamp,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
-pub struct ExecutionSucceededEventDetails {
-    pub output: String,
+#[derive(Debug, Serialize)]
+pub struct RerunExecutionResponse {
+    #[serde(flatten)]
+    pub started: StartExecutionResponse,
+    #[serde(rename = "originalExecutionArn")]
+    pub original_execution_arn: String,
+    /// Route of this server that returns the original execution's history.
+    #[serde(rename = "originalHistory")]
+    pub original_history: String,
 }
 
 #[derive(Deserialize, Serialize)]
-pub struct EventResponse {
-    pub events: Vec<Event>,
+pub struct ExecutionsResponse {
+    pub executions: Vec<Executions>,
+    /// Present when more executions can be fetched with `?nextToken=`.
+    #[serde(rename = "nextToken", default, skip_serializing_if = "Option::is_none")]
+    pub next_token: Option<String>,
 }
 
-#[derive(Serialize)]
-pub struct ServerError {
-    pub message: String,
+/// Query string of `GET /{region}/{arn}/executions`.
+#[derive(Debug, Default, Deserialize)]
+pub struct ExecutionsQuery {
+    pub status: Option<String>,
+    pub limit: Option<u32>,
+    #[serde(rename = "nextToken")]
+    pub next_token: Option<String>,
 }
 
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
-pub enum Type {
-    Task,
-    Pass,
-    Choice,
-    Fail,
-    Succeed,
-    Wait,
-    Map,
-    Parallel,
+/// Query string of `DELETE /{region}/{arn}/state-machine`.
+#[derive(Debug, Default, Deserialize)]
+pub struct DeleteStateMachineQuery {
+    /// Report what would be deleted without deleting it.
+    #[serde(rename = "dryRun", default)]
+    pub dry_run: bool,
+    /// Delete even if executions are still running.
+    #[serde(default)]
+    pub force: bool,
 }
 
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
-pub struct Choice {
-    #[serde(rename = "Variable")]
-    variable: String,
-
-    #[serde(rename = "IsPresent")]
-    is_present: Option<bool>,
-
-    #[serde(rename = "BooleanEquals")]
-    bool_equals: Option<bool>,
-
-    #[serde(rename = "Next")]
-    pub next: Option<String>,
+/// Query string of `GET /{region}/{arn}/causes/chain`.
+#[derive(Debug, Deserialize)]
+pub struct CauseChainQuery {
+    #[serde(rename = "eventId")]
+    pub event_id: u64,
 }
 
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
-pub struct Catch {
-    #[serde(rename = "ErrorEquals")]
-    pub error_equals: Vec<String>,
-    #[serde(rename = "Next")]
-    pub next: String,
-    #[serde(rename = "ResultPath")]
-    pub result_path: String,
+#[derive(Debug, Serialize)]
+pub struct DeleteStateMachineResponse {
+    #[serde(rename = "stateMachineArn")]
+    pub state_machine_arn: String,
+    #[serde(rename = "runningExecutions")]
+    pub running_executions: usize,
+    #[serde(rename = "dryRun")]
+    pub dry_run: bool,
+    pub deleted: bool,
 }
 
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
-pub struct Step {
-    #[serde(rename = "Type")]
-    pub step_type: Type,
-
-    #[serde(rename = "End")]
-    pub end: Option<bool>,
-
-    #[serde(rename = "Next")]
-    pub next: Option<String>,
-
-    #[serde(rename = "Resource")]
-    pub resource: Option<Value>,
-
-    #[serde(rename = "ResultPath")]
-    pub result_path: Option<String>,
+/// Body of `POST /{region}/{arn}/stop-executions`.
+#[derive(Debug, Default, Deserialize)]
+pub struct BulkStopRequest {
+    pub error: Option<String>,
+    pub cause: Option<String>,
+    /// Only stop running executions whose name starts with this.
+    #[serde(rename = "namePrefix")]
+    pub name_prefix: Option<String>,
+}
 
-    #[serde(rename = "Choices")]
-    pub choices: Option<Vec<Choice>>,
+/// Body of `POST /{region}/state-machines/delete`.
+#[derive(Debug, Default, Deserialize)]
+pub struct BulkDeleteRequest {
+    #[serde(rename = "stateMachineArns")]
+    pub state_machine_arns: Vec<String>,
+    #[serde(rename = "dryRun", default)]
+    pub dry_run: bool,
+    #[serde(default)]
+    pub force: bool,
+}
 
-    #[serde(rename = "Catch")]
-    pub catch: Option<Vec<Catch>>,
+/// The outcome of one item of a bulk operation: `result` on success,
+/// `error` otherwise, with the status a single call would have returned.
+#[derive(Serialize)]
+pub struct BulkItem<T> {
+    pub arn: String,
+    pub status: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<T>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<ServerError>,
+}
 
-    #[serde(rename = "Default")]
-    pub default: Option<String>,
+#[derive(Serialize)]
+pub struct BulkResponse<T> {
+    pub succeeded: usize,
+    pub failed: usize,
+    pub results: Vec<BulkItem<T>>,
 }
 
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
-pub struct StateMachineDefinition {
-    #[serde(rename = "Comment")]
-    pub comment: String,
-    #[serde(rename = "StartAt")]
-    pub start_at: String,
-    #[serde(rename = "States")]
-    pub states: BTreeMap<String, Step>,
+#[derive(Serialize)]
+pub struct ServerError {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+    pub message: String,
+    /// What the aws CLI printed when it failed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stderr: Option<String>,
 }
 
 #[derive(Debug, PartialEq, Serialize, Deserialize)]
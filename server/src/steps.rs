@@ -0,0 +1,335 @@
+//! State visits reconstructed from a history, with the data that flowed
+//! through them.
+//!
+//! Events of Map iterations and Parallel branches interleave, so visits are
+//! paired and placed by following `previousEventId` rather than by event order.
+
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+use crate::asl::graph::Graph;
+use crate::history::{Event, EventDetails, EventIndex};
+use crate::timestamp::Timestamp;
+
+/// The Map iteration or Parallel branch a state ran in.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Scope {
+    /// Name of the Map or Parallel state.
+    pub state: String,
+    /// Id of that state's `StateEntered` event.
+    #[serde(rename = "enteredEventId")]
+    pub entered_event_id: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub iteration: Option<u64>,
+    /// Index into `Branches`; absent if the definition does not say.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub branch: Option<usize>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Step {
+    pub name: String,
+    #[serde(rename = "enteredEventId")]
+    pub entered_event_id: u64,
+    pub entered: Timestamp,
+    /// When the state exited, or was aborted; only an exit has an output.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exited: Option<Timestamp>,
+    #[serde(rename = "durationMillis", skip_serializing_if = "Option::is_none")]
+    pub duration_millis: Option<i64>,
+    pub input: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output: Option<String>,
+    /// The last failure inside the visit, caught, retried or fatal.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cause: Option<String>,
+    pub retries: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent: Option<Scope>,
+}
+
+/// Events that start an attempt at a state's work; more than one means retries.
+fn is_attempt(event: &Event) -> bool {
+    matches!(
+        event.details,
+        EventDetails::TaskScheduled { .. }
+            | EventDetails::LambdaFunctionScheduled { .. }
+            | EventDetails::ActivityScheduled { .. }
+            | EventDetails::ParallelStateStarted
+            | EventDetails::MapStateStarted { .. }
+    )
+}
+
+/// Branch indexes of Parallel states by (state name, branch `StartAt`).
+pub fn branch_indexes(graph: &Graph) -> HashMap<(&str, &str), usize> {
+    let mut indexes = HashMap::new();
+    for node in graph.all_nodes() {
+        if node.kind != "Parallel" {
+            continue;
+        }
+        for (index, subgraph) in node.subgraphs.iter().enumerate() {
+            for start in subgraph.graph.nodes.iter().filter(|child| child.start) {
+                indexes.insert((node.name.as_str(), start.name.as_str()), index);
+            }
+        }
+    }
+    indexes
+}
+
+/// The innermost Map iteration or Parallel branch the visit that starts
+/// with `entered` ran in. Completed states on the way back are skipped
+/// whole, so a state after a Parallel is not mistaken for part of it.
+pub fn scope(
+    index: &EventIndex,
+    branches: &HashMap<(&str, &str), usize>,
+    entered: &Event,
+) -> Option<Scope> {
+    let mut child = entered;
+    let mut current = index.previous(entered);
+    while let Some(event) = current {
+        let (iteration, branch) = match &event.details {
+            EventDetails::MapIterationStarted { details } => (details.index, None),
+            EventDetails::ParallelStateStarted => (None, child.state_entered()),
+            _ if event.state_exited().is_some() => {
+                // Jump over the finished visit to whatever caused it.
+                child = index.entered_state(event)?;
+                current = index.previous(child);
+                continue;
+            }
+            _ => {
+                child = event;
+                current = index.previous(event);
+                continue;
+            }
+        };
+        let owner = index.entered_state(event)?;
+        let state = owner.state_entered()?.name.clone();
+        let branch = branch.and_then(|first| {
+            branches
+                .get(&(state.as_str(), first.name.as_str()))
+                .copied()
+        });
+        return Some(Scope {
+            state,
+            entered_event_id: owner.id,
+            iteration,
+            branch,
+        });
+    }
+    None
+}
+
+/// Every state visit in the order the states were entered.
+pub fn steps(graph: &Graph, events: &[Event]) -> Vec<Step> {
+    let index = EventIndex::new(events);
+    let branches = branch_indexes(graph);
+    let mut ordered: Vec<&Event> = events.iter().collect();
+    ordered.sort_by_key(|event| event.id);
+
+    let mut steps: Vec<Step> = Vec::new();
+    let mut attempts: Vec<usize> = Vec::new();
+    let mut step_of_entry: HashMap<u64, usize> = HashMap::new();
+    for event in ordered {
+        if let Some(entered) = event.state_entered() {
+            step_of_entry.insert(event.id, steps.len());
+            attempts.push(0);
+            steps.push(Step {
+                name: entered.name.clone(),
+                entered_event_id: event.id,
+                entered: event.timestamp,
+                exited: None,
+                duration_millis: None,
+                input: entered.input.clone(),
+                output: None,
+                error: None,
+                cause: None,
+                retries: 0,
+                parent: scope(&index, &branches, event),
+            });
+            continue;
+        }
+        let Some(&step) = index
+            .entered_state(event)
+            .and_then(|entered| step_of_entry.get(&entered.id))
+        else {
+            continue;
+        };
+        if let Some(exited) = event.state_exited() {
+            let step = &mut steps[step];
+            step.exited = Some(event.timestamp);
+            step.duration_millis = Some(event.timestamp.millis_since(&step.entered));
+            step.output = Some(exited.output.clone());
+        } else if event.is_state_aborted() {
+            let step = &mut steps[step];
+            step.exited = Some(event.timestamp);
+            step.duration_millis = Some(event.timestamp.millis_since(&step.entered));
+        } else if let Some(failure) = event.failure() {
+            steps[step].error = failure.error.map(str::to_string);
+            steps[step].cause = failure.cause.map(str::to_string);
+        } else if is_attempt(event) {
+            attempts[step] += 1;
+            steps[step].retries = attempts[step].saturating_sub(1);
+        }
+    }
+    steps
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::asl::graph::DefinitionGraph;
+    use crate::asl::lenient;
+    use serde_json::{json, Value};
+
+    fn event(id: u64, previous: u64, kind: &str, details: Option<(&str, Value)>) -> Value {
+        let mut event = json!({
+            "timestamp": format!("2024-03-01T10:00:{:02}Z", id),
+            "type": kind,
+            "id": id,
+            "previousEventId": previous
+        });
+        if let Some((key, details)) = details {
+            event[key] = details;
+        }
+        event
+    }
+
+    fn entered(id: u64, previous: u64, kind: &str, name: &str, input: &str) -> Value {
+        event(
+            id,
+            previous,
+            &format!("{kind}StateEntered"),
+            Some((
+                "stateEnteredEventDetails",
+                json!({"name": name, "input": input}),
+            )),
+        )
+    }
+
+    fn exited(id: u64, previous: u64, kind: &str, name: &str, output: &str) -> Value {
+        event(
+            id,
+            previous,
+            &format!("{kind}StateExited"),
+            Some((
+                "stateExitedEventDetails",
+                json!({"name": name, "output": output}),
+            )),
+        )
+    }
+
+    fn graph() -> Graph {
+        let document = include_str!("../fixtures/asl/parallel.asl.json");
+        DefinitionGraph::new(lenient::parse(document).unwrap()).graph
+    }
+
+    #[test]
+    fn interleaved_branches_keep_their_data() {
+        let task = json!({"resourceType": "lambda", "resource": "invoke", "region": "us-east-1", "parameters": "{}"});
+        let failed = json!({"resourceType": "lambda", "resource": "invoke", "error": "Sms.Busy", "cause": "try later"});
+        let history: Vec<Event> = serde_json::from_value(json!([
+            entered(2, 1, "Parallel", "Notify", r#"{"id":7}"#),
+            event(3, 2, "ParallelStateStarted", None),
+            entered(4, 3, "Task", "SendEmail", r#"{"orderId":7}"#),
+            entered(5, 3, "Task", "SendSms", r#"{"orderId":7}"#),
+            event(
+                6,
+                4,
+                "TaskScheduled",
+                Some(("taskScheduledEventDetails", task.clone()))
+            ),
+            event(
+                7,
+                5,
+                "TaskScheduled",
+                Some(("taskScheduledEventDetails", task.clone()))
+            ),
+            event(8, 7, "TaskFailed", Some(("taskFailedEventDetails", failed))),
+            event(
+                9,
+                8,
+                "TaskScheduled",
+                Some(("taskScheduledEventDetails", task))
+            ),
+            exited(10, 6, "Task", "SendEmail", r#""mailed""#),
+            exited(11, 9, "Task", "SendSms", r#""texted""#),
+            entered(12, 11, "Succeed", "SmsSent", r#""texted""#),
+            exited(13, 12, "Succeed", "SmsSent", r#""texted""#),
+            event(14, 13, "ParallelStateSucceeded", None),
+            exited(15, 14, "Parallel", "Notify", r#"["mailed","texted"]"#),
+            entered(16, 15, "Pass", "Done", "{}"),
+        ]))
+        .unwrap();
+        let steps = steps(&graph(), &history);
+        let names: Vec<&str> = steps.iter().map(|step| step.name.as_str()).collect();
+        assert_eq!(names, ["Notify", "SendEmail", "SendSms", "SmsSent", "Done"]);
+
+        let email = &steps[1];
+        assert_eq!(email.input, r#"{"orderId":7}"#);
+        assert_eq!(email.output.as_deref(), Some(r#""mailed""#));
+        assert_eq!(email.parent.as_ref().unwrap().branch, Some(0));
+
+        let sms = &steps[2];
+        assert_eq!(sms.output.as_deref(), Some(r#""texted""#));
+        assert_eq!(sms.retries, 1);
+        assert_eq!(sms.error.as_deref(), Some("Sms.Busy"));
+        assert_eq!(sms.duration_millis, Some(6000));
+        assert_eq!(steps[3].parent.as_ref().unwrap().branch, Some(1));
+
+        assert_eq!(steps[0].parent, None);
+        assert_eq!(steps[4].parent, None);
+        assert_eq!(steps[4].output, None);
+    }
+
+    #[test]
+    fn map_iterations_are_numbered() {
+        let history: Vec<Event> = serde_json::from_value(json!([
+            entered(2, 1, "Map", "Each", "[1,2]"),
+            event(
+                3,
+                2,
+                "MapStateStarted",
+                Some(("mapStateStartedEventDetails", json!({"length": 2})))
+            ),
+            event(
+                4,
+                3,
+                "MapIterationStarted",
+                Some((
+                    "mapIterationStartedEventDetails",
+                    json!({"name": "Each", "index": 0})
+                ))
+            ),
+            event(
+                5,
+                3,
+                "MapIterationStarted",
+                Some((
+                    "mapIterationStartedEventDetails",
+                    json!({"name": "Each", "index": 1})
+                ))
+            ),
+            entered(6, 5, "Pass", "Work", "2"),
+            entered(7, 4, "Pass", "Work", "1"),
+        ]))
+        .unwrap();
+        let steps = steps(&Graph::default(), &history);
+        let iterations: Vec<(&str, Option<u64>)> = steps
+            .iter()
+            .map(|step| {
+                (
+                    step.input.as_str(),
+                    step.parent.as_ref().and_then(|scope| scope.iteration),
+                )
+            })
+            .collect();
+        assert_eq!(
+            iterations,
+            [("[1,2]", None), ("2", Some(1)), ("1", Some(0))]
+        );
+        assert_eq!(steps[1].parent.as_ref().unwrap().entered_event_id, 2);
+    }
+}
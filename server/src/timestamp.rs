@@ -0,0 +1,134 @@
+//! Instants as returned by either backend.
+//!
+//! The JSON protocol sends epoch seconds with a fractional part, the CLI sends
+//! ISO-8601 strings with an offset. Both become a `Timestamp`, which is always
+//! sent to the frontend as RFC 3339 with milliseconds.
+
+use std::fmt;
+
+use chrono::{DateTime, FixedOffset, NaiveDateTime, SecondsFormat};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value;
+
+/// Offset-less forms some tools emit; they are taken to be UTC.
+const NAIVE_FORMATS: [&str; 3] = [
+    "%Y-%m-%dT%H:%M:%S%.f",
+    "%Y-%m-%d %H:%M:%S%.f",
+    "%Y-%m-%d %H:%M",
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Timestamp(DateTime<FixedOffset>);
+
+impl Timestamp {
+    pub fn now() -> Self {
+        Timestamp(chrono::Utc::now().fixed_offset())
+    }
+
+    pub fn from_epoch_seconds(seconds: f64) -> Option<Self> {
+        if !seconds.is_finite() {
+            return None;
+        }
+        let millis = (seconds * 1000.0).round() as i64;
+        DateTime::from_timestamp_millis(millis).map(|date| Timestamp(date.fixed_offset()))
+    }
+
+    pub fn parse(text: &str) -> Option<Self> {
+        if let Ok(date) = DateTime::parse_from_rfc3339(text) {
+            return Some(Timestamp(date));
+        }
+        NAIVE_FORMATS.iter().find_map(|format| {
+            NaiveDateTime::parse_from_str(text, format)
+                .ok()
+                .map(|date| Timestamp(date.and_utc().fixed_offset()))
+        })
+    }
+
+    /// Milliseconds from `earlier` to `self`; negative if `earlier` is later.
+    pub fn millis_since(&self, earlier: &Timestamp) -> i64 {
+        (self.0 - earlier.0).num_milliseconds()
+    }
+}
+
+impl fmt::Display for Timestamp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0.to_rfc3339_opts(SecondsFormat::Millis, true))
+    }
+}
+
+impl Serialize for Timestamp {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Timestamp {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        match Value::deserialize(deserializer)? {
+            Value::Number(seconds) => seconds
+                .as_f64()
+                .and_then(Timestamp::from_epoch_seconds)
+                .ok_or_else(|| de::Error::custom(format!("invalid epoch timestamp {seconds}"))),
+            Value::String(text) => Timestamp::parse(&text)
+                .ok_or_else(|| de::Error::custom(format!("invalid timestamp {text:?}"))),
+            other => Err(de::Error::custom(format!("invalid timestamp {other}"))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn timestamp(value: Value) -> Timestamp {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn epoch_seconds_keep_their_milliseconds() {
+        let parsed = timestamp(serde_json::json!(1699612567.763));
+        assert_eq!(parsed.to_string(), "2023-11-10T10:36:07.763Z");
+    }
+
+    #[test]
+    fn iso_strings_keep_their_offset() {
+        let parsed = timestamp(serde_json::json!("2023-11-10T12:36:07.763000+02:00"));
+        assert_eq!(parsed.to_string(), "2023-11-10T12:36:07.763+02:00");
+        assert_eq!(parsed, timestamp(serde_json::json!(1699612567.763)));
+        assert_eq!(
+            serde_json::to_value(parsed).unwrap(),
+            "2023-11-10T12:36:07.763+02:00"
+        );
+    }
+
+    #[test]
+    fn offsetless_strings_are_utc() {
+        assert_eq!(
+            timestamp(serde_json::json!("2023-11-10 10:36")).to_string(),
+            "2023-11-10T10:36:00.000Z"
+        );
+        assert_eq!(
+            timestamp(serde_json::json!("2023-11-10T10:36:07.5")).to_string(),
+            "2023-11-10T10:36:07.500Z"
+        );
+    }
+
+    #[test]
+    fn garbage_is_rejected() {
+        for value in [
+            serde_json::json!("yesterday"),
+            serde_json::json!(true),
+            serde_json::json!(null),
+        ] {
+            assert!(serde_json::from_value::<Timestamp>(value).is_err());
+        }
+    }
+
+    #[test]
+    fn durations_span_offsets() {
+        let start = timestamp(serde_json::json!("2023-11-10T12:36:07.763+02:00"));
+        let stop = timestamp(serde_json::json!(1699612569.013));
+        assert_eq!(stop.millis_since(&start), 1250);
+        assert_eq!(start.millis_since(&stop), -1250);
+    }
+}
@@ -0,0 +1,318 @@
+//! An execution's history laid over its definition graph.
+//!
+//! Every event is attributed to a state visit by following `previousEventId`
+//! back to the nearest `StateEntered`, which keeps interleaved Map and
+//! Parallel events apart.
+
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+use crate::asl::graph::Graph;
+use crate::history::{Event, EventIndex};
+use crate::timestamp::Timestamp;
+
+/// Ordered by severity: a state visited several times reports its worst visit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum VisitStatus {
+    NotVisited,
+    Succeeded,
+    /// Failed at least once, then succeeded on a retry.
+    Retried,
+    /// Failed and left through a Catch.
+    Caught,
+    /// Stopped before it exited, with the execution or an enclosing state.
+    Aborted,
+    Running,
+    Failed,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct StateTrace {
+    /// Node id in the definition graph; absent for states the definition lacks.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    pub name: String,
+    pub status: VisitStatus,
+    #[serde(rename = "visitCount")]
+    pub visit_count: usize,
+    #[serde(rename = "firstEntered", skip_serializing_if = "Option::is_none")]
+    pub first_entered: Option<Timestamp>,
+    #[serde(rename = "lastExited", skip_serializing_if = "Option::is_none")]
+    pub last_exited: Option<Timestamp>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Trace {
+    pub states: Vec<StateTrace>,
+}
+
+struct Visit<'a> {
+    name: &'a str,
+    entered: Timestamp,
+    exited: Option<Timestamp>,
+    failures: usize,
+    failed_last: bool,
+    aborted: bool,
+}
+
+impl Visit<'_> {
+    fn status(&self, finished: bool) -> VisitStatus {
+        match self.exited {
+            Some(_) if self.failures == 0 => VisitStatus::Succeeded,
+            Some(_) if self.failed_last => VisitStatus::Caught,
+            Some(_) => VisitStatus::Retried,
+            None if self.aborted => VisitStatus::Aborted,
+            None if finished => VisitStatus::Failed,
+            None => VisitStatus::Running,
+        }
+    }
+}
+
+/// One entry per node of `graph`, in graph order, followed by any state
+/// the history visits that the graph does not have.
+pub fn trace(graph: &Graph, events: &[Event]) -> Trace {
+    let index = EventIndex::new(events);
+    let mut ordered: Vec<&Event> = events.iter().collect();
+    ordered.sort_by_key(|event| event.id);
+
+    let mut visits: Vec<Visit> = Vec::new();
+    let mut visit_of_entry: HashMap<u64, usize> = HashMap::new();
+    let mut finished = false;
+    for event in ordered {
+        if let Some(entered) = event.state_entered() {
+            visit_of_entry.insert(event.id, visits.len());
+            visits.push(Visit {
+                name: &entered.name,
+                entered: event.timestamp,
+                exited: None,
+                failures: 0,
+                failed_last: false,
+                aborted: false,
+            });
+            continue;
+        }
+        finished |= event.is_execution_end();
+        let Some(visit) = index
+            .entered_state(event)
+            .and_then(|entered| visit_of_entry.get(&entered.id))
+            .map(|&visit| &mut visits[visit])
+        else {
+            continue;
+        };
+        if event.state_exited().is_some() {
+            visit.exited = Some(event.timestamp);
+        } else if event.is_state_aborted() {
+            visit.aborted = true;
+        } else if event.failure().is_some() {
+            visit.failures += 1;
+            visit.failed_last = true;
+        } else if event.is_success() {
+            visit.failed_last = false;
+        }
+    }
+
+    let mut by_name: HashMap<&str, StateTrace> = HashMap::new();
+    let mut unknown: Vec<&str> = Vec::new();
+    for visit in &visits {
+        let state = by_name.entry(visit.name).or_insert_with(|| {
+            unknown.push(visit.name);
+            StateTrace {
+                id: None,
+                name: visit.name.to_string(),
+                status: VisitStatus::NotVisited,
+                visit_count: 0,
+                first_entered: None,
+                last_exited: None,
+            }
+        });
+        state.visit_count += 1;
+        state.status = state.status.max(visit.status(finished));
+        state.first_entered = Some(
+            state
+                .first_entered
+                .map_or(visit.entered, |first| first.min(visit.entered)),
+        );
+        state.last_exited = state.last_exited.max(visit.exited);
+    }
+
+    let mut states: Vec<StateTrace> = graph
+        .all_nodes()
+        .into_iter()
+        .map(|node| {
+            let visited = by_name.remove(node.name.as_str());
+            StateTrace {
+                id: Some(node.id.clone()),
+                ..visited.unwrap_or(StateTrace {
+                    id: None,
+                    name: node.name.clone(),
+                    status: VisitStatus::NotVisited,
+                    visit_count: 0,
+                    first_entered: None,
+                    last_exited: None,
+                })
+            }
+        })
+        .collect();
+    states.extend(unknown.into_iter().filter_map(|name| by_name.remove(name)));
+    Trace { states }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::asl::graph::DefinitionGraph;
+    use crate::asl::lenient;
+    use serde_json::{json, Value};
+
+    fn events(events: Value) -> Vec<Event> {
+        serde_json::from_value(events).unwrap()
+    }
+
+    fn status_of<'a>(trace: &'a Trace, name: &str) -> &'a StateTrace {
+        trace
+            .states
+            .iter()
+            .find(|state| state.name == name)
+            .unwrap()
+    }
+
+    fn entered(id: u64, previous: u64, kind: &str, name: &str) -> Value {
+        json!({
+            "timestamp": format!("2024-03-01T10:00:{:02}Z", id),
+            "type": format!("{kind}StateEntered"),
+            "id": id,
+            "previousEventId": previous,
+            "stateEnteredEventDetails": {"name": name, "input": "{}"}
+        })
+    }
+
+    fn exited(id: u64, previous: u64, kind: &str, name: &str) -> Value {
+        json!({
+            "timestamp": format!("2024-03-01T10:00:{:02}Z", id),
+            "type": format!("{kind}StateExited"),
+            "id": id,
+            "previousEventId": previous,
+            "stateExitedEventDetails": {"name": name, "output": "{}"}
+        })
+    }
+
+    fn task(id: u64, previous: u64, kind: &str) -> Value {
+        let details = json!({
+            "resourceType": "lambda",
+            "resource": "invoke",
+            "region": "us-east-1",
+            "parameters": "{}"
+        });
+        let key = format!("task{}EventDetails", &kind["Task".len()..]);
+        json!({
+            "timestamp": format!("2024-03-01T10:00:{:02}Z", id),
+            "type": kind,
+            "id": id,
+            "previousEventId": previous,
+            key: details
+        })
+    }
+
+    fn graph() -> Graph {
+        let document = json!({
+            "StartAt": "Charge",
+            "States": {
+                "Charge": {
+                    "Type": "Task",
+                    "Resource": "arn:aws:states:::lambda:invoke",
+                    "Retry": [{"ErrorEquals": ["States.ALL"]}],
+                    "Next": "Ship"
+                },
+                "Ship": {
+                    "Type": "Task",
+                    "Resource": "arn:aws:states:::lambda:invoke",
+                    "Catch": [{"ErrorEquals": ["States.ALL"], "Next": "Refund"}],
+                    "Next": "Done"
+                },
+                "Refund": {"Type": "Pass", "Next": "Notify"},
+                "Notify": {"Type": "Task", "Resource": "arn:aws:states:::sns:publish", "End": true},
+                "Done": {"Type": "Succeed"}
+            }
+        });
+        DefinitionGraph::new(lenient::parse(&document.to_string()).unwrap()).graph
+    }
+
+    #[test]
+    fn visits_are_classified() {
+        let history = events(json!([
+            {"timestamp": "2024-03-01T10:00:01Z", "type": "ExecutionStarted", "id": 1,
+             "executionStartedEventDetails": {"input": "{}", "roleArn": "role"}},
+            entered(2, 1, "Task", "Charge"),
+            task(3, 2, "TaskScheduled"),
+            task(4, 3, "TaskFailed"),
+            task(5, 4, "TaskScheduled"),
+            task(6, 5, "TaskSucceeded"),
+            exited(7, 6, "Task", "Charge"),
+            entered(8, 7, "Task", "Ship"),
+            task(9, 8, "TaskScheduled"),
+            task(10, 9, "TaskFailed"),
+            exited(11, 10, "Task", "Ship"),
+            entered(12, 11, "Pass", "Refund"),
+            exited(13, 12, "Pass", "Refund"),
+            entered(14, 13, "Task", "Notify"),
+            task(15, 14, "TaskScheduled"),
+        ]));
+        let trace = trace(&graph(), &history);
+
+        assert_eq!(status_of(&trace, "Charge").status, VisitStatus::Retried);
+        assert_eq!(status_of(&trace, "Ship").status, VisitStatus::Caught);
+        assert_eq!(status_of(&trace, "Refund").status, VisitStatus::Succeeded);
+        assert_eq!(status_of(&trace, "Notify").status, VisitStatus::Running);
+        assert_eq!(status_of(&trace, "Done").status, VisitStatus::NotVisited);
+        assert_eq!(
+            status_of(&trace, "Done").id.as_deref(),
+            Some("/States/Done")
+        );
+
+        let charge = status_of(&trace, "Charge");
+        assert_eq!(charge.visit_count, 1);
+        assert_eq!(
+            charge.first_entered.unwrap().to_string(),
+            "2024-03-01T10:00:02.000Z"
+        );
+        assert_eq!(
+            charge.last_exited.unwrap().to_string(),
+            "2024-03-01T10:00:07.000Z"
+        );
+    }
+
+    #[test]
+    fn unfinished_states_of_a_failed_execution_failed() {
+        let history = events(json!([
+            entered(2, 1, "Task", "Charge"),
+            task(3, 2, "TaskScheduled"),
+            task(4, 3, "TaskFailed"),
+            {"timestamp": "2024-03-01T10:00:05Z", "type": "ExecutionFailed", "id": 5,
+             "previousEventId": 4, "executionFailedEventDetails": {"error": "Boom"}},
+            entered(6, 1, "Pass", "Elsewhere"),
+        ]));
+        let trace = trace(&graph(), &history);
+        assert_eq!(status_of(&trace, "Charge").status, VisitStatus::Failed);
+        let last = trace.states.last().unwrap();
+        assert_eq!(
+            (last.name.as_str(), last.id.as_deref()),
+            ("Elsewhere", None)
+        );
+    }
+
+    #[test]
+    fn aborted_tasks_are_not_left_running() {
+        let history = events(json!([
+            entered(2, 1, "Task", "Charge"),
+            task(3, 2, "TaskScheduled"),
+            {"timestamp": "2024-03-01T10:00:04Z", "type": "TaskStateAborted", "id": 4,
+             "previousEventId": 3},
+            {"timestamp": "2024-03-01T10:00:05Z", "type": "ExecutionAborted", "id": 5,
+             "previousEventId": 4, "executionAbortedEventDetails": {}},
+        ]));
+        let trace = trace(&graph(), &history);
+        assert_eq!(status_of(&trace, "Charge").status, VisitStatus::Aborted);
+    }
+}
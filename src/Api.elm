@@ -72,7 +72,7 @@ stopRunningExecution (Region region) arn =
     RemoteHttp.postWithConfig jsonConfig
         (baseUrl ++ "/" ++ region ++ "/" ++ arn ++ "/stop-execution")
         HandlePostMachine
-        Jdec.int
+        (Jdec.field "stopDate" Jdec.string)
         Json.Encode.null
 
 
@@ -116,7 +116,7 @@ choiceStateDecoder =
 descibeStateMachineDecoder : Jdec.Decoder StateMachineDescriptor
 descibeStateMachineDecoder =
     Jdec.map3 StateMachineDescriptor
-        (Jdec.field "Comment" Jdec.string)
+        (Jdec.maybe (Jdec.field "Comment" Jdec.string))
         (Jdec.field "StartAt" Jdec.string)
         (Jdec.field "States" <| Jdec.dict stateDecoder)
 
@@ -219,7 +219,7 @@ type StateMachineState
 
 
 type alias StateMachineDescriptor =
-    { comment : String
+    { comment : Maybe String
     , startAt : String
     , states : Dict String StateMachineState
     }
@@ -328,7 +328,7 @@ type Msg
     | SelectGraphExecution Execution
     | SelectView Active
     | HandleDeleteStateMachine (WebData String)
-    | HandlePostMachine (WebData Int)
+    | HandlePostMachine (WebData String)
     | DeleteStateMachine Region String
     | StopRunningExecution Region String
     | ClearNotification UUID